@@ -2,21 +2,27 @@ use std::mem::{replace, take};
 
 use egui::{Align, Color32, Event, Label, Layout, PointerButton, PopupAnchor, Rect, Response, RichText, Sense, Stroke, StrokeKind, Tooltip, Vec2b};
 use egui_extras::Column;
+use itertools::Itertools;
 use tap::prelude::{Pipe, Tap};
 
 use crate::{
-    viewer::{EmptyRowCreateContext, RowViewer},
+    io::TextFormat,
+    source::{RowSource, SourceTable},
+    viewer::{CustomActionOrigin, CustomMenuItem, EmptyRowCreateContext, RowGroup, RowViewer},
     DataTable, UiAction,
 };
 
+use self::find::{FindRequest, FindState};
 use self::state::*;
 
 use format as f;
 use std::sync::Arc;
 use egui::scroll_area::ScrollBarVisibility;
 
+pub(crate) mod csv;
+pub(crate) mod find;
 pub(crate) mod state;
-mod tsv;
+pub(crate) mod tsv;
 
 /* -------------------------------------------- Style ------------------------------------------- */
 
@@ -62,6 +68,28 @@ pub struct Style {
 
     /// See ['ScrollArea::ScrollBarVisibility`] for details.
     pub scroll_bar_visibility: ScrollBarVisibility,
+
+    /// When enabled, the body stays scrolled to the bottom as rows are added, as in a log
+    /// viewer. Scrolling up pauses it, until the body is scrolled back to the bottom.
+    pub follow_tail: bool,
+
+    /// When enabled, a row of filter editors is rendered under the header, for every column
+    /// which [`RowViewer::is_filterable_column`] returns true.
+    pub show_filter_row: bool,
+
+    /// When enabled, a footer row showing [`RowViewer::column_aggregate`] of each column is
+    /// rendered below the body. Aggregates are computed over selected cells if more than one
+    /// cell is selected, or every visible row otherwise.
+    pub show_footer: bool,
+
+    /// When enabled, a status bar summarizing number of rows and current selection is rendered
+    /// at the bottom.
+    pub show_status_bar: bool,
+
+    /// Format of copied contents written to the system clipboard. Default is escaped TSV; use
+    /// [`TextFormat::QuotedTsv`] for better interoperability with spreadsheet applications.
+    /// Pasted contents are always decoded by auto-detected format.
+    pub clipboard_format: TextFormat,
 }
 
 /* ------------------------------------------ Rendering ----------------------------------------- */
@@ -71,9 +99,16 @@ pub struct Renderer<'a, R, V: RowViewer<R>> {
     viewer: &'a mut V,
     state: Option<Box<UiState<R>>>,
     style: Style,
-    translator: Arc<dyn Translator>
+    translator: Arc<dyn Translator>,
+
+    /// Set by [`Renderer::with_undo_history_persistency`].
+    #[cfg(feature = "persistency")]
+    undo_persistency: Option<UndoPersistencyFn<R, V>>,
 }
 
+#[cfg(feature = "persistency")]
+type UndoPersistencyFn<R, V> = fn(&mut UiState<R>, &egui::Context, egui::Id, &[R], &mut V);
+
 impl<R, V: RowViewer<R>> egui::Widget for Renderer<'_, R, V> {
     fn ui(self, ui: &mut egui::Ui) -> Response {
         self.show(ui)
@@ -86,6 +121,21 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             table.push(viewer.new_empty_row_for(EmptyRowCreateContext::InsertNewLine));
         }
 
+        Self::with_table(table, viewer)
+    }
+
+    /// Renders rows of [`SourceTable`], fetching the ones scrolled into view on the next frame.
+    /// Sort and filters configured by user are applied by the source, and modifications of rows
+    /// are sent back to it, on the next frame as well.
+    pub fn from_source<S: RowSource<R>>(
+        source: &'a mut SourceTable<R, S>,
+        viewer: &'a mut V,
+    ) -> Self {
+        source.sync(viewer);
+        Self::with_table(source.table_mut(), viewer)
+    }
+
+    fn with_table(table: &'a mut DataTable<R>, viewer: &'a mut V) -> Self {
         Self {
             state: Some(table.ui.take().unwrap_or_default().tap_mut(|state| {
                 state.validate_identity(viewer);
@@ -94,6 +144,8 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             viewer,
             style: Default::default(),
             translator: Arc::new(EnglishTranslator::default()),
+            #[cfg(feature = "persistency")]
+            undo_persistency: None,
         }
     }
 
@@ -142,215 +194,178 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
         self
     }
 
+    /// Keeps undo history in egui memory, so that it survives app restarts along with other
+    /// persisted egui states. This is independent from [`RowViewer::persist_ui_state`].
+    ///
+    /// Stored history is restored only if the table has the same number of rows; it is the
+    /// application's responsibility to restore identical row data.
+    #[cfg(feature = "persistency")]
+    pub fn with_undo_history_persistency(mut self) -> Self
+    where
+        R: serde::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.undo_persistency = Some(UiState::validate_undo_persistency);
+        self
+    }
+
     pub fn show(self, ui: &mut egui::Ui) -> Response {
-        egui::ScrollArea::horizontal()
-            .show(ui, |ui| self.impl_show(ui))
-            .inner
+        self.impl_show(ui)
     }
 
     fn impl_show(mut self, ui: &mut egui::Ui) -> Response {
-        let ctx = &ui.ctx().clone();
-        let ui_id = ui.id();
-        let style = ui.style().clone();
-        let painter = ui.painter().clone();
-        let visual = &style.visuals;
-        let viewer = &mut *self.viewer;
-        let s = self.state.as_mut().unwrap();
-        let mut resp_total = None::<Response>;
-        let mut resp_ret = None::<Response>;
-        let mut commands = Vec::<Command<R>>::new();
-        let ui_layer_id = ui.layer_id();
-
-        // NOTE: unlike RED and YELLOW which can be acquirable through 'error_bg_color' and
-        // 'warn_bg_color', there's no 'green' color which can be acquired from inherent theme.
-        // Following logic simply gets 'green' color from current background's brightness.
-        let green = if visual.window_fill.g() > 128 {
-            Color32::DARK_GREEN
-        } else {
-            Color32::GREEN
-        };
-
-        let mut builder = egui_extras::TableBuilder::new(ui).column(Column::auto());
-
-        let iter_vis_cols_with_flag = s
-            .vis_cols()
-            .iter()
-            .enumerate()
-            .map(|(index, column)| (column, index + 1 == s.vis_cols().len()));
-
-        for (column, flag) in iter_vis_cols_with_flag {
-            builder = builder.column(viewer.column_render_config(column.0, flag));
-        }
+        let mut frame = self.begin_frame(ui);
 
-        if replace(&mut s.cci_want_move_scroll, false) {
-            let interact_row = s.interactive_cell().0;
-            builder = builder.scroll_to_row(interact_row.0, None);
+        // Status bar takes the bottom side of the space first.
+        if self.style.show_status_bar {
+            egui::Panel::bottom(frame.ui_id.with("status-bar"))
+                .frame(egui::Frame::NONE)
+                .show(ui, |ui| self.show_status_bar(ui));
         }
 
-        builder
-            .columns(Column::auto(), s.num_columns() - s.vis_cols().len())
-            .drag_to_scroll(egui::scroll_area::DragScroll::Never) // Drag is used for selection;
-            .striped(true)
-            .cell_layout(egui::Layout::default().with_cross_align(self.style.cell_align))
-            .max_scroll_height(f32::MAX)
-            .auto_shrink(self.style.auto_shrink)
-            .scroll_bar_visibility(self.style.scroll_bar_visibility)
-            .sense(Sense::click_and_drag().tap_mut(|s| s.set(Sense::FOCUSABLE, true)))
-            .header(20., |mut h| {
-                h.col(|_ui| {
-                    // TODO: Add `Configure Sorting` button
-                });
-
-                let has_any_hidden_col = s.vis_cols().len() != s.num_columns();
-
-                for (vis_col, &col) in s.vis_cols().iter().enumerate() {
-                    let vis_col = VisColumnPos(vis_col);
-                    let mut painter = None;
-                    let (col_rect, resp) = h.col(|ui| {
-                        egui::Sides::new().show(ui, |ui| {
-                            ui.add(Label::new(viewer.column_name(col.0))
-                                .selectable(false)
-                            );
-                        }, |ui|{
-                            if let Some(pos) = s.sort().iter().position(|(c, ..)| c == &col) {
-                                let is_asc = s.sort()[pos].1 .0 as usize;
-
-                                ui.colored_label(
-                                    [green, Color32::RED][is_asc],
-                                    RichText::new(format!("{}{}", ["↘", "↗"][is_asc], pos + 1,))
-                                        .monospace(),
-                                );
-                            } else {
-                                // calculate the maximum width for the sort indicator
-                                let max_sort_indicator_width = (s.num_columns() + 1).to_string().len() + 1;
-                                // when the sort indicator is present, create a label the same size as the sort indicator
-                                // so that the columns don't resize when sorted.
-                                ui.add(Label::new(RichText::new(" ".repeat(max_sort_indicator_width)).monospace()).selectable(false));
-                            }
-                        });
-
-                        painter = Some(ui.painter().clone());
-                    });
-
-                    // Set drag payload for column reordering.
-                    resp.dnd_set_drag_payload(vis_col);
+        // Checkout `cc_rows` to satisfy borrow checker. We need to access to
+        // state mutably within row rendering; therefore, we can't simply borrow
+        // `cc_rows` during the whole logic!
+        let s = self.state.as_mut().unwrap();
+        let cc_row_heights = take(&mut s.cc_row_heights);
+        let num_vis_cols = s.vis_cols().len();
 
-                    if resp.dragged() {
-                        Tooltip::always_open(ctx.clone(), ui_layer_id, "_EGUI_DATATABLE__COLUMN_MOVE__".into(), PopupAnchor::Pointer)
-                            .gap(12.0)
-                            .show(|ui|{
-                                let colum_name = viewer.column_name(col.0);
-                                ui.label(colum_name);
-                            });
-                    }
+        // Heights of rendered rows; group headers are placed in between `cc_rows`.
+        let grouped_row_heights;
+        let row_heights = if s.num_display_rows() == cc_row_heights.len() {
+            &cc_row_heights
+        } else {
+            grouped_row_heights = (0..s.num_display_rows())
+                .map(|index| match s.display_row(index) {
+                    DisplayRow::Group(_) => 20.,
+                    DisplayRow::Row(vis_row) => cc_row_heights[vis_row.0],
+                })
+                .collect::<Vec<_>>();
+            &grouped_row_heights
+        };
 
-                    if resp.hovered() && viewer.is_sortable_column(col.0) {
-                        if let Some(p) = &painter {
-                            p.rect_filled(
-                                col_rect,
-                                egui::CornerRadius::ZERO,
-                                visual.selection.bg_fill.gamma_multiply(0.2),
-                            );
-                        }
-                    }
+        let num_frozen_cols = s.frozen_columns();
+        let num_frozen_rows = s.frozen_rows();
 
-                    if viewer.is_sortable_column(col.0) && resp.clicked_by(PointerButton::Primary) {
-                        let mut sort = s.sort().to_owned();
-                        match sort.iter_mut().find(|(c, ..)| c == &col) {
-                            Some((_, asc)) => match asc.0 {
-                                true => asc.0 = false,
-                                false => sort.retain(|(c, ..)| c != &col),
-                            },
-                            None => {
-                                sort.push((col, IsAscending(true)));
-                            }
-                        }
+        if num_frozen_cols == 0 {
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                let group = PaneGroup {
+                    cols: 0..num_vis_cols,
+                    row_header: true,
+                    sync_offset: None,
+                };
 
-                        commands.push(Command::SetColumnSort(sort));
-                    }
+                self.show_pane_group(ui, &group, num_frozen_rows, row_heights, &mut frame)
+            });
+        } else {
+            // Frozen columns are rendered as a separate table on the left side, which follows
+            // vertical scroll of the right one.
+            let prev_offset = s.cci_scroll_offset;
+            let layout = Layout::left_to_right(Align::Min);
+            let (left_offset, right_offset) = ui
+                .allocate_ui_with_layout(ui.available_size(), layout, |ui| {
+                    let left = ui
+                        .push_id("frozen-columns", |ui| {
+                            let group = PaneGroup {
+                                cols: 0..num_frozen_cols,
+                                row_header: true,
+                                sync_offset: Some(prev_offset),
+                            };
+
+                            self.show_pane_group(
+                                ui,
+                                &group,
+                                num_frozen_rows,
+                                row_heights,
+                                &mut frame,
+                            )
+                        })
+                        .inner;
+
+                    let right = egui::ScrollArea::horizontal()
+                        .show(ui, |ui| {
+                            let group = PaneGroup {
+                                cols: num_frozen_cols..num_vis_cols,
+                                row_header: false,
+                                sync_offset: Some(prev_offset),
+                            };
+
+                            self.show_pane_group(
+                                ui,
+                                &group,
+                                num_frozen_rows,
+                                row_heights,
+                                &mut frame,
+                            )
+                        })
+                        .inner;
 
-                    if resp.dnd_hover_payload::<VisColumnPos>().is_some() {
-                        if let Some(p) = &painter {
-                            p.rect_filled(
-                                col_rect,
-                                egui::CornerRadius::ZERO,
-                                visual.selection.bg_fill.gamma_multiply(0.5),
-                            );
-                        }
-                    }
+                    (left, right)
+                })
+                .inner;
 
-                    if let Some(payload) = resp.dnd_release_payload::<VisColumnPos>() {
-                        commands.push(Command::CcReorderColumn {
-                            from: *payload,
-                            to: vis_col
-                                .0
-                                .pipe(|v| v + (payload.0 < v) as usize)
-                                .pipe(VisColumnPos),
-                        })
-                    }
+            // Whichever pane scrolled by user input leads the other one.
+            let new_offset = if right_offset != prev_offset {
+                right_offset
+            } else {
+                left_offset
+            };
 
-                    resp.context_menu(|ui| {
-                        if ui.button(self.translator.translate("context-menu-hide")).clicked() {
-                            commands.push(Command::CcHideColumn(col));
-                        }
+            if new_offset != prev_offset {
+                self.state.as_mut().unwrap().cci_scroll_offset = new_offset;
+                frame.ctx.request_repaint();
+            }
+        }
 
-                        if !s.sort().is_empty() && ui.button(self.translator.translate("context-menu-clear-sort")).clicked() {
-                            commands.push(Command::SetColumnSort(Vec::new()));
-                        }
+        if self.state.as_ref().unwrap().find.is_open {
+            let table_rect = ui.min_rect().intersect(ui.clip_rect());
+            self.show_find(table_rect, &mut frame);
+        }
 
-                        if has_any_hidden_col {
-                            ui.separator();
-                            ui.label(self.translator.translate("context-menu-hidden"));
-
-                            for col in (0..s.num_columns()).map(ColumnIdx) {
-                                if !s.vis_cols().contains(&col)
-                                    && ui.button(viewer.column_name(col.0)).clicked()
-                                {
-                                    commands.push(Command::CcShowColumn {
-                                        what: col,
-                                        at: vis_col,
-                                    });
-                                }
-                            }
-                        }
-                    });
-                }
+        let num_col_groups = if num_frozen_cols == 0 { 1 } else { 2 };
+        self.end_frame(frame, cc_row_heights, num_col_groups)
+    }
 
-                // Account for header response to calculate total response.
-                resp_total = Some(h.response());
-            })
-            .tap_mut(|table| {
-                table.ui_mut().separator();
+    /// Renders find & replace overlay at the top-right corner of the table.
+    fn show_find(&mut self, table_rect: Rect, frame: &mut FrameState<R>) {
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let table = &*self.table;
+
+        let (request, has_focus) = egui::Area::new(frame.ui_id.with("find"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::RIGHT_TOP)
+            .fixed_pos(table_rect.right_top() + egui::vec2(-8., 8.))
+            .show(&frame.ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .show(ui, |ui| show_find_overlay(ui, &mut s.find, &*self.translator))
+                    .inner
             })
-            .body(|body: egui_extras::TableBody<'_>| {
-                resp_ret = Some(
-                    self.impl_show_body(body, painter, commands, ctx, &style, ui_id, resp_total),
-                );
-            });
+            .inner;
+
+        if has_focus {
+            // Keep table hotkeys from consuming the editor's input.
+            s.cci_has_focus = false;
+        }
 
-        resp_ret.unwrap_or_else(|| ui.label("??"))
+        frame.commands.extend(match request {
+            Some(FindRequest::Next) => s.find_next(table, viewer, false),
+            Some(FindRequest::Previous) => s.find_next(table, viewer, true),
+            Some(FindRequest::Replace) => s.find_replace(table, viewer, false),
+            Some(FindRequest::ReplaceAll) => s.find_replace(table, viewer, true),
+            None => return,
+        });
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn impl_show_body(
-        &mut self,
-        body: egui_extras::TableBody<'_>,
-        mut _painter: egui::Painter,
-        mut commands: Vec<Command<R>>,
-        ctx: &egui::Context,
-        style: &egui::Style,
-        ui_id: egui::Id,
-        mut resp_total: Option<Response>,
-    ) -> Response {
+    /// Handles inputs and validates caches, before rendering any pane.
+    fn begin_frame(&mut self, ui: &egui::Ui) -> FrameState<R> {
+        let ctx = ui.ctx();
+        let ui_id = ui.id();
+        let style = ui.style().clone();
         let viewer = &mut *self.viewer;
         let s = self.state.as_mut().unwrap();
         let table = &mut *self.table;
-        let visual = &style.visuals;
-        let visible_cols = s.vis_cols().clone();
-        let no_rounding = egui::CornerRadius::ZERO;
 
         let mut actions = Vec::<UiAction>::new();
-        let mut edit_started = false;
         let hotkeys = viewer.hotkeys(&s.ui_action_context());
 
         // Preemptively consume all hotkeys.
@@ -409,491 +424,1305 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             s.validate_persistency(ctx, ui_id, viewer);
         }
 
-        // Validate ui state. Defer this as late as possible; since it may not be
-        // called if the table area is out of the visible space.
+        #[cfg(feature = "persistency")]
+        if let Some(validate) = self.undo_persistency {
+            validate(s, ctx, ui_id.with("undo-history"), &table.rows, viewer);
+        }
+
+        // Validate ui state. Every pane relies on it, thus it can't be deferred to body rendering.
         s.validate_cc(&mut table.rows, viewer);
 
-        // Checkout `cc_rows` to satisfy borrow checker. We need to access to
-        // state mutably within row rendering; therefore, we can't simply borrow
-        // `cc_rows` during the whole logic!
-        let cc_row_heights = take(&mut s.cc_row_heights);
+        if self.style.show_footer || self.style.show_status_bar {
+            s.validate_footer(table, viewer);
+        }
 
-        let mut row_height_updates = Vec::new();
-        let vis_row_digits = s.cc_rows.len().max(1).ilog10();
-        let row_id_digits = table.len().max(1).ilog10();
+        s.validate_group_aggregates(table, viewer);
 
-        let body_max_rect = body.max_rect();
-        let has_any_sort = !s.sort().is_empty();
+        // NOTE: unlike RED and YELLOW which can be acquirable through 'error_bg_color' and
+        // 'warn_bg_color', there's no 'green' color which can be acquired from inherent theme.
+        // Following logic simply gets 'green' color from current background's brightness.
+        let green = if style.visuals.window_fill.g() > 128 {
+            Color32::DARK_GREEN
+        } else {
+            Color32::GREEN
+        };
 
-        let pointer_interact_pos = ctx.input(|i| i.pointer.latest_pos().unwrap_or_default());
-        let pointer_primary_down = ctx.input(|i| i.pointer.button_down(PointerButton::Primary));
+        let show_filter_row = self.style.show_filter_row
+            && s.vis_cols().iter().any(|c| viewer.is_filterable_column(c.0));
 
         s.cci_page_row_count = 0;
+        let num_vis_cols = s.vis_cols().len();
+
+        FrameState {
+            commands: Vec::new(),
+            actions,
+            custom_actions: Vec::new(),
+            reset_column_widths: s.validate_column_layout(),
+            column_widths: vec![0.; num_vis_cols],
+            is_resizing: false,
+            content_widths: vec![0.; num_vis_cols],
+            auto_fit: None,
+            hotkeys,
+            edit_started: false,
+            resp_total: None,
+            measured_row_heights: Default::default(),
+            scroll_to_row: replace(&mut s.cci_want_move_scroll, false)
+                .then(|| s.display_index(s.interactive_cell().0)),
+            header_height: if show_filter_row { 44. } else { 20. },
+            show_filter_row,
+            green,
+            vis_row_digits: s.cc_rows.len().max(1).ilog10(),
+            row_id_digits: table.len().max(1).ilog10(),
+            has_any_sort: !s.sort().is_empty(),
+            pointer_interact_pos: ctx.input(|i| i.pointer.latest_pos().unwrap_or_default()),
+            pointer_primary_down: ctx.input(|i| i.pointer.button_down(PointerButton::Primary)),
+            layer_id: ui.layer_id(),
+            ui_id,
+            ctx: ctx.clone(),
+            style,
+        }
+    }
 
-        /* ----------------------------- Primary Rendering Function ----------------------------- */
-        // - Extracted as a closure to differentiate behavior based on row height
-        //   configuration. (heterogeneous or homogeneous row heights)
-
-        let render_fn = |mut row: egui_extras::TableRow| {
-            s.cci_page_row_count += 1;
-
-            let vis_row = VisRowPos(row.index());
-            let row_id = s.cc_rows[vis_row.0];
-            let prev_row_height = cc_row_heights[vis_row.0];
-
-            let mut row_elem_start = Default::default();
-
-            // Check if current row is edition target
-            let edit_state = s.row_editing_cell(row_id);
-            let mut editing_cell_rect = Rect::NOTHING;
-            let interactive_row = s.is_interactive_row(vis_row);
-
-            let check_mouse_dragging_selection = {
-                let s_cci_has_focus = s.cci_has_focus;
-                let s_cci_has_selection = s.has_cci_selection();
+    /// Renders columns of the group; frozen rows on top, then scrolling rows. Returns vertical
+    /// scroll offset of the scrolling rows.
+    fn show_pane_group(
+        &mut self,
+        ui: &mut egui::Ui,
+        group: &PaneGroup,
+        num_frozen_rows: usize,
+        row_heights: &[f32],
+        frame: &mut FrameState<R>,
+    ) -> f32 {
+        let num_rows = row_heights.len();
+        let footer_height = match self.style.show_footer {
+            true => self.state.as_ref().unwrap().cci_footer_height,
+            false => 0.,
+        };
 
-                move |rect: &Rect, resp: &egui::Response| {
-                    let cci_hovered: bool = s_cci_has_focus
-                        && s_cci_has_selection
-                        && rect
-                            .with_max_x(resp.rect.right())
-                            .contains(pointer_interact_pos);
-                    let sel_drag = cci_hovered && pointer_primary_down;
-                    let sel_click = !s_cci_has_selection && resp.hovered() && pointer_primary_down;
+        // Leaves space for the footer below the scrolling rows.
+        let body_size = |ui: &egui::Ui| {
+            egui::vec2(ui.available_width(), (ui.available_height() - footer_height).max(0.))
+        };
 
-                    sel_drag || sel_click
-                }
+        let (widths, offset) = if num_frozen_rows == 0 {
+            let pane = Pane {
+                rows: 0..num_rows,
+                header: true,
+                scroll: true,
             };
 
-            /* -------------------------------- Header Rendering -------------------------------- */
+            ui.allocate_ui(body_size(ui), |ui| {
+                self.show_pane(ui, group, &pane, None, row_heights, frame)
+            })
+            .inner
+        } else {
+            let top = Pane {
+                rows: 0..num_frozen_rows,
+                header: true,
+                scroll: false,
+            };
+            let (widths, _) = self.show_pane(ui, group, &top, None, row_heights, frame);
 
-            // Mark row background filled if being edited.
-            row.set_selected(edit_state.is_some());
+            // Scrolling rows follow column widths of the frozen rows, which own the header.
+            let bottom = Pane {
+                rows: num_frozen_rows..num_rows,
+                header: false,
+                scroll: true,
+            };
 
-            // Render row header button
-            let (head_rect, head_resp) = row.col(|ui| {
-                // Calculate the position where values start.
-                row_elem_start = ui.max_rect().right_top();
+            ui.push_id("scrolling-rows", |ui| {
+                ui.allocate_ui(body_size(ui), |ui| {
+                    self.show_pane(ui, group, &bottom, Some(&widths), row_heights, frame)
+                })
+                .inner
+            })
+            .inner
+        };
 
-                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    ui.separator();
+        if self.style.show_footer {
+            self.show_footer(ui, group, &widths);
+        }
 
-                    if has_any_sort {
-                        ui.monospace(
-                            RichText::from(f!(
-                                "{:·>width$}",
-                                row_id.0,
-                                width = row_id_digits as usize
-                            ))
-                            .strong(),
-                        );
-                    } else {
-                        ui.monospace(
-                            RichText::from(f!("{:>width$}", "", width = row_id_digits as usize))
-                                .strong(),
-                        );
-                    }
+        offset
+    }
 
-                    ui.monospace(
-                        RichText::from(f!(
-                            "{:·>width$}",
-                            vis_row.0 + 1,
-                            width = vis_row_digits as usize
-                        ))
-                        .weak(),
-                    );
-                });
-            });
+    /// Renders aggregates of each column, with the given column widths.
+    fn show_footer(&mut self, ui: &mut egui::Ui, group: &PaneGroup, widths: &[f32]) {
+        let s = self.state.as_mut().unwrap();
+        let Some(footer) = s.footer() else {
+            return;
+        };
 
-            if check_mouse_dragging_selection(&head_rect, &head_resp) {
-                s.cci_sel_update_row(vis_row);
-            }
+        let top = ui.cursor().min.y;
+        ui.separator();
 
-            /* -------------------------------- Columns Rendering ------------------------------- */
-
-            // Overridable maximum height
-            let mut new_maximum_height = 0.;
-
-            // Render cell contents regardless of the edition state.
-            for (vis_col, col) in visible_cols.iter().enumerate() {
-                let vis_col = VisColumnPos(vis_col);
-                let linear_index = vis_row.linear_index(visible_cols.len(), vis_col);
-                let selected = s.is_selected(vis_row, vis_col);
-                let cci_selected = s.is_selected_cci(vis_row, vis_col);
-                let is_editing = edit_state.is_some();
-                let is_interactive_cell = interactive_row.is_some_and(|x| x == vis_col);
-                let mut response_consumed = s.is_editing();
-
-                let (rect, resp) = row.col(|ui| {
-                    let ui_max_rect = ui.max_rect();
-
-                    if cci_selected {
-                        ui.painter().rect_stroke(
-                            ui_max_rect,
-                            no_rounding,
-                            Stroke {
-                                width: 2.,
-                                color: self
-                                    .style
-                                    .fg_drag_selection
-                                    .unwrap_or(visual.selection.bg_fill),
-                            },
-                            StrokeKind::Inside,
-                        );
-                    }
+        let mut builder = egui_extras::TableBuilder::new(ui)
+            .id_salt("footer")
+            .vscroll(false)
+            .cell_layout(egui::Layout::default().with_cross_align(self.style.cell_align));
 
-                    if is_interactive_cell {
-                        ui.painter().rect_filled(
-                            ui_max_rect.expand(2.),
-                            no_rounding,
-                            self.style
-                                .bg_selected_highlight_cell
-                                .unwrap_or(visual.selection.bg_fill),
-                        );
-                    } else if selected {
-                        ui.painter().rect_filled(
-                            ui_max_rect.expand(1.),
-                            no_rounding,
-                            self.style
-                                .bg_selected_cell
-                                .unwrap_or(visual.selection.bg_fill.gamma_multiply(0.5)),
-                        );
-                    }
+        for width in widths {
+            builder = builder.column(Column::exact(*width).resizable(false));
+        }
 
-                    // Actual widget rendering happens within this line.
+        builder.body(|mut body| {
+            body.row(self.style.table_row_height.unwrap_or(20.), |mut row| {
+                if group.row_header {
+                    row.col(|ui| {
+                        let tooltip = match footer.is_selection {
+                            true => "footer-aggregates-selection",
+                            false => "footer-aggregates-rows",
+                        };
 
-                    // ui.set_enabled(false);
-                    ui.style_mut()
-                        .visuals
-                        .widgets
-                        .noninteractive
-                        .fg_stroke
-                        .color = if is_interactive_cell {
-                        self.style
-                            .fg_selected_highlight_cell
-                            .unwrap_or(visual.strong_text_color())
-                    } else {
-                        visual.strong_text_color()
-                    };
+                        ui.strong("Σ")
+                            .on_hover_text(self.translator.translate(tooltip));
+                    });
+                }
 
-                    // FIXME: After egui 0.27, now the widgets spawned inside this closure
-                    // intercepts interactions, which is basically natural behavior(Upper layer
-                    // widgets). However, this change breaks current implementation which relies on
-                    // the previous table behavior.
-                    ui.add_enabled_ui(true, |ui| {
-                        if !(is_editing && is_interactive_cell) {
-                            viewer.show_cell_view(ui, &table.rows[row_id.0], col.0);
+                for vis_col in group.cols.clone() {
+                    row.col(|ui| {
+                        if let Some(text) = &footer.aggregates[vis_col] {
+                            ui.strong(text);
                         }
                     });
+                }
+            });
+        });
 
-                    #[cfg(any())]
-                    if selected {
-                        ui.painter().rect_stroke(
-                            ui_max_rect,
-                            no_rounding,
-                            Stroke {
-                                width: 1.,
-                                color: visual.weak_text_color(),
-                            },
-                        );
-                    }
+        // Measure for the next frame, to reserve the space beforehand.
+        let height = ui.cursor().min.y - top;
+        if s.cci_footer_height != height {
+            s.cci_footer_height = height;
+            ui.ctx().request_repaint();
+        }
+    }
 
-                    if interactive_row.is_some() && !is_editing {
-                        let st = Stroke {
-                            width: 1.,
-                            color: self
-                                .style
-                                .focused_row_stroke
-                                .unwrap_or(visual.warn_fg_color.gamma_multiply(0.5)),
-                        };
+    /// Renders summary of the table and current selection.
+    fn show_status_bar(&self, ui: &mut egui::Ui) {
+        let s = self.state.as_ref().unwrap();
+        let Some(footer) = s.footer() else {
+            return;
+        };
 
-                        let xr = ui_max_rect.x_range();
-                        let yr = ui_max_rect.y_range();
-                        ui.painter().hline(xr, yr.min, st);
-                        ui.painter().hline(xr, yr.max, st);
-                    }
+        let tr = |key| self.translator.translate(key);
 
-                    if edit_state.is_some_and(|(_, vis)| vis == vis_col) {
-                        editing_cell_rect = ui_max_rect;
-                    }
-                });
+        ui.horizontal(|ui| {
+            ui.label(f!("{}: {} / {}", tr("status-bar-rows"), s.cc_rows.len(), self.table.len()));
 
-                new_maximum_height = rect.height().max(new_maximum_height);
+            if footer.num_selected_cells > 1 {
+                ui.separator();
+                ui.label(f!("{}: {}", tr("status-bar-selected-cells"), footer.num_selected_cells));
+                ui.separator();
+                ui.label(f!("{}: {}", tr("status-bar-selected-rows"), footer.num_selected_rows));
+            }
+        });
+    }
 
-                // -- Mouse Actions --
-                if check_mouse_dragging_selection(&rect, &resp) {
-                    // Expand cci selection
-                    response_consumed = true;
-                    s.cci_sel_update(linear_index);
-                }
+    /// Renders a pane as a single table. Returns (column widths, vertical scroll offset).
+    fn show_pane(
+        &mut self,
+        ui: &mut egui::Ui,
+        group: &PaneGroup,
+        pane: &Pane,
+        exact_widths: Option<&[f32]>,
+        row_heights: &[f32],
+        frame: &mut FrameState<R>,
+    ) -> (Vec<f32>, f32) {
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let num_vis_cols = s.vis_cols().len();
+        let is_last_group = group.cols.end == num_vis_cols;
+
+        // Resize handles of column borders aren't exposed by egui_extras; this mirrors the id it
+        // derives for them, `state_id.with("resize_column").with(column)`, where `state_id` is
+        // the table's id. The version of egui_extras is pinned for this reason.
+        let resize_id = ui
+            .id()
+            .with(egui::IdSalt::new("table"))
+            .with("resize_column");
+        let mut builder = egui_extras::TableBuilder::new(ui).id_salt("table");
+
+        if let Some(widths) = exact_widths {
+            for width in widths {
+                builder = builder.column(Column::exact(*width).resizable(false));
+            }
+        } else {
+            if frame.reset_column_widths {
+                builder.reset();
+            }
 
-                let editable = viewer.is_editable_cell(vis_col.0, vis_row.0, &table.rows[row_id.0]);
+            if group.row_header {
+                builder = builder.column(Column::auto());
+            }
 
-                if editable
-                    && (resp.clicked_by(PointerButton::Primary)
-                        && (self.style.single_click_edit_mode || is_interactive_cell))
-                {
-                    response_consumed = true;
-                    commands.push(Command::CcEditStart(
-                        row_id,
-                        vis_col,
-                        viewer.clone_row(&table.rows[row_id.0]).into(),
-                    ));
-                    edit_started = true;
-                }
+            for vis_col in group.cols.clone() {
+                let column = s.vis_cols()[vis_col];
+                let is_last = vis_col + 1 == num_vis_cols;
 
-                /* --------------------------- Context Menu Rendering --------------------------- */
+                // Content is clipped to respect the width chosen by user.
+                builder = builder.column(match s.column_width(column).filter(|_| !is_last) {
+                    Some(width) => Column::initial(width).clip(true).resizable(true),
+                    None => viewer.column_render_config(column.0, is_last),
+                });
+            }
 
-                (resp.clone() | head_resp.clone()).context_menu(|ui| {
-                    response_consumed = true;
-                    ui.set_min_size(egui::vec2(250., 10.));
+            if is_last_group {
+                builder = builder.columns(Column::auto(), s.num_columns() - num_vis_cols);
+            }
+        }
 
-                    if !selected {
-                        commands.push(Command::CcSetSelection(vec![VisSelection(
-                            linear_index,
-                            linear_index,
-                        )]));
-                    } else if !is_interactive_cell {
-                        s.set_interactive_cell(vis_row, vis_col);
-                    }
+        if pane.scroll
+            && let Some(row) = frame.scroll_to_row
+            && pane.rows.contains(&row)
+        {
+            builder = builder.scroll_to_row(row - pane.rows.start, None);
+        }
 
-                    let sel_multi_row = s.cursor_as_selection().is_some_and(|sel| {
-                        let mut min = usize::MAX;
-                        let mut max = usize::MIN;
+        if pane.scroll {
+            builder = builder.stick_to_bottom(self.style.follow_tail);
+        }
 
-                        for sel in sel {
-                            min = min.min(sel.0 .0);
-                            max = max.max(sel.1 .0);
-                        }
+        if pane.scroll
+            && let Some(offset) = group.sync_offset
+        {
+            builder = builder.vertical_scroll_offset(offset).animate_scrolling(false);
+        }
 
-                        let (r_min, _) = VisLinearIdx(min).row_col(s.vis_cols().len());
-                        let (r_max, _) = VisLinearIdx(max).row_col(s.vis_cols().len());
+        let scroll_bar_visibility = if is_last_group {
+            self.style.scroll_bar_visibility
+        } else {
+            ScrollBarVisibility::AlwaysHidden
+        };
 
-                        r_min != r_max
-                    });
+        let mut auto_shrink = self.style.auto_shrink;
+        auto_shrink.x |= !is_last_group;
+        auto_shrink.y |= !pane.scroll;
 
-                    let cursor_x = ui.cursor().min.x;
-                    let clip = s.has_clipboard_contents();
-                    let b_undo = s.has_undo();
-                    let b_redo = s.has_redo();
-                    let mut n_sep_menu = 0;
-                    let mut draw_sep = false;
-
-                    let context_menu_items = [
-                        Some((selected, "🖻", "context-menu-selection-copy", UiAction::CopySelection)),
-                        Some((selected, "🖻", "context-menu-selection-cut", UiAction::CutSelection)),
-                        Some((selected, "🗙", "context-menu-selection-clear", UiAction::DeleteSelection)),
-                        Some((
-                            sel_multi_row,
-                            "🗐",
-                            "context-menu-selection-fill",
-                            UiAction::SelectionDuplicateValues,
-                        )),
-                        None,
-                        Some((clip, "➿", "context-menu-clipboard-paste", UiAction::PasteInPlace)),
-                        Some((
-                            clip && viewer.allow_row_insertions(),
-                            "🛠",
-                            "context-menu-clipboard-insert",
-                            UiAction::PasteInsert,
-                        )),
-                        None,
-                        Some((
-                            viewer.allow_row_insertions(),
-                            "🗐",
-                            "context-menu-row-duplicate",
-                            UiAction::DuplicateRow,
-                        )),
-                        Some((
-                            viewer.allow_row_deletions(),
-                            "🗙",
-                            "context-menu-row-delete",
-                            UiAction::DeleteRow,
-                        )),
-                        None,
-                        Some((b_undo, "⎗", "context-menu-undo", UiAction::Undo)),
-                        Some((b_redo, "⎘", "context-menu-redo", UiAction::Redo)),
-                    ];
-                    
-                    // Render built-in items
-                    for opt in context_menu_items {
-                        if let Some((icon, key, action)) =
-                            opt.filter(|x| x.0).map(|x| (x.1, x.2, x.3))
-                        {
-                            if draw_sep {
-                                draw_sep = false;
-                                ui.separator();
-                            }
+        let builder = builder
+            .drag_to_scroll(egui::scroll_area::DragScroll::Never) // Drag is used for selection;
+            .striped(true)
+            .cell_layout(egui::Layout::default().with_cross_align(self.style.cell_align))
+            .vscroll(pane.scroll)
+            .max_scroll_height(f32::MAX)
+            .auto_shrink(auto_shrink)
+            .scroll_bar_visibility(scroll_bar_visibility)
+            .sense(Sense::click_and_drag().tap_mut(|s| s.set(Sense::FOCUSABLE, true)));
+
+        let mut widths: Vec<f32> = Vec::new();
+        let output = if pane.header {
+            builder
+                .header(frame.header_height, |h| self.show_header(h, group, frame))
+                .tap_mut(|table| {
+                    table.ui_mut().separator();
+                })
+                .body(|body| {
+                    widths = body.widths().to_vec();
+                    self.show_pane_body(body, group, pane, row_heights, frame)
+                })
+        } else {
+            builder.body(|body| {
+                widths = body.widths().to_vec();
+                self.show_pane_body(body, group, pane, row_heights, frame)
+            })
+        };
 
-                            let hotkey = hotkeys
-                                .iter()
-                                .find_map(|(k, a)| (a == &action).then(|| ctx.format_shortcut(k)));
+        if exact_widths.is_none() {
+            let offset = group.row_header as usize;
 
-                            ui.horizontal(|ui| {
-                                ui.monospace(icon);
-                                ui.add_space(cursor_x + 20. - ui.cursor().min.x);
+            for (index, vis_col) in group.cols.clone().enumerate() {
+                frame.column_widths[vis_col] = widths.get(offset + index).copied().unwrap_or(0.);
 
-                                let label = self.translator.translate(key);
-                                let btn = egui::Button::new(label)
-                                    .shortcut_text(hotkey.unwrap_or_else(|| "🗙".into()));
-                                let r = ui.centered_and_justified(|ui| ui.add(btn)).inner;
+                let Some(resp) = frame.ctx.read_response(resize_id.with(offset + index)) else {
+                    continue;
+                };
 
-                                if r.clicked() {
-                                    actions.push(action);
-                                }
-                            });
+                frame.is_resizing |= resp.dragged();
 
-                            n_sep_menu += 1;
-                        } else if n_sep_menu > 0 {
-                            n_sep_menu = 0;
-                            draw_sep = true;
-                        }
-                    }
+                // Double click on a column border fits the column on its left. The last column
+                // fills the remaining width instead, as in the header context menu.
+                if resp.double_clicked() && vis_col + 1 < num_vis_cols {
+                    frame.auto_fit = Some(VisColumnPos(vis_col));
+                }
+            }
+        }
 
-                    // Render custom items contributed by the viewer
-                    let ui_ctx = s.ui_action_context();
-                    let selection_snapshot = {
-                        // Build a lightweight snapshot to pass into the callback
-                        let mut selected_rows = Vec::new();
-                        if let Some(sels) = s.cursor_as_selection() {
-                            let mut rows = std::collections::BTreeSet::new();
-                            for sel in sels.iter() {
-                                let (top, _) = sel.0.row_col(s.vis_cols().len());
-                                let (bottom, _) = sel.1.row_col(s.vis_cols().len());
-                                for r in top.0..=bottom.0 { rows.insert(r); }
-                            }
-                            for r in rows { let row_id = s.cc_rows[r].0; selected_rows.push((row_id, &table.rows[row_id])); }
-                        }
+        (widths, output.state.offset.y)
+    }
 
-                        let mut selected_cells = Vec::new();
-                        if let Some(sels) = s.cursor_as_selection() {
-                            for sel in sels.iter() {
-                                let (top, left) = sel.0.row_col(s.vis_cols().len());
-                                let (bottom, right) = sel.1.row_col(s.vis_cols().len());
-                                for r in top.0..=bottom.0 {
-                                    for c in left.0..=right.0 {
-                                        let row_id = s.cc_rows[r].0;
-                                        let col = s.vis_cols()[c].0;
-                                        selected_cells.push((row_id, col));
-                                    }
-                                }
-                            }
+    fn show_header(
+        &mut self,
+        mut h: egui_extras::TableRow<'_, '_>,
+        group: &PaneGroup,
+        frame: &mut FrameState<R>,
+    ) {
+        let ctx = &frame.ctx;
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let visual = &frame.style.visuals;
+        let commands = &mut frame.commands;
+        let show_filter_row = frame.show_filter_row;
+        let green = frame.green;
+
+        if group.row_header {
+            h.col(|ui| {
+                let resp = ui
+                    .horizontal(|ui| {
+                        if s.is_rebuilding() {
+                            // Previous rows are shown until they're rebuilt in background.
+                            ui.add(egui::Spinner::new().size(12.)).on_hover_text(
+                                self.translator.translate("sort-config-rebuilding"),
+                            );
                         }
 
-                        let (ic_r, ic_c) = s.interactive_cell();
-                        let interactive_cell = if s.cc_rows.is_empty() { None } else { Some((s.cc_rows[ic_r.0].0, s.vis_cols()[ic_c.0].0)) };
+                        ui.add(egui::Button::new("⇅").small().frame(false))
+                            .on_hover_text(self.translator.translate("sort-config-tooltip"))
+                    })
+                    .inner;
+
+                egui::Popup::from_toggle_button_response(&resp)
+                    .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+                    .show(|ui| {
+                        let new_sort = show_sort_config(
+                            ui,
+                            viewer,
+                            s.sort(),
+                            s.num_columns(),
+                            &*self.translator,
+                        );
 
-                        crate::viewer::SelectionSnapshot {
-                            selected_rows,
-                            selected_cells,
-                            interactive_cell,
-                            visible_columns: s.vis_cols().len(),
-                        }
-                    };
-                    // origin_cell is passed during dispatch from state; nothing to do here.
-
-                    let custom_items = viewer.custom_context_menu_items(&ui_ctx, &selection_snapshot);
-                    if !custom_items.is_empty() {
-                        ui.separator();
-                        for item in custom_items {
-                            if !item.enabled { continue; }
-                            ui.horizontal(|ui| {
-                                if let Some(icon) = item.icon { ui.monospace(icon); }
-                                let btn = egui::Button::new(item.label);
-                                let r = ui.centered_and_justified(|ui| ui.add(btn)).inner;
-                                if r.clicked() { actions.push(UiAction::Custom(item.id)); }
-                            });
+                        if let Some(sort) = new_sort {
+                            commands.push(Command::SetColumnSort(sort));
                         }
+                    });
+
+                if show_filter_row && s.has_column_filters() {
+                    ui.add_space(20. - resp.rect.height());
+
+                    if ui
+                        .small_button("🗙")
+                        .on_hover_text(self.translator.translate("filter-row-clear"))
+                        .clicked()
+                    {
+                        s.clear_column_filters();
+                    }
+                }
+            });
+        }
+
+        let has_any_hidden_col = s.vis_cols().len() != s.num_columns();
+        let vis_cols = s.vis_cols()[group.cols.clone()].to_vec();
+
+        for (vis_col, col) in group.cols.clone().zip(vis_cols) {
+            let vis_col = VisColumnPos(vis_col);
+            let mut painter = None;
+            let mut content_width = 0.;
+            let (col_rect, resp) = h.col(|ui| {
+                let (name, indicator) = egui::Sides::new().show(ui, |ui| {
+                    ui.add(Label::new(viewer.column_name(col.0))
+                        .selectable(false)
+                    )
+                }, |ui|{
+                    if let Some(pos) = s.sort().iter().position(|(c, ..)| c == &col) {
+                        let is_asc = s.sort()[pos].1 .0 as usize;
+
+                        ui.colored_label(
+                            [green, Color32::RED][is_asc],
+                            RichText::new(format!("{}{}", ["↘", "↗"][is_asc], pos + 1,))
+                                .monospace(),
+                        )
+                    } else {
+                        // calculate the maximum width for the sort indicator
+                        let max_sort_indicator_width = (s.num_columns() + 1).to_string().len() + 1;
+                        // when the sort indicator is present, create a label the same size as the sort indicator
+                        // so that the columns don't resize when sorted.
+                        ui.add(Label::new(RichText::new(" ".repeat(max_sort_indicator_width)).monospace()).selectable(false))
                     }
                 });
 
-                // Forward DnD event if not any event was consumed by the response.
+                content_width =
+                    name.rect.width() + ui.spacing().item_spacing.x + indicator.rect.width();
 
-                // FIXME: Upgrading egui 0.29 make interaction rectangle of response object
-                // larger(in y axis) than actually visible column cell size. To deal with this,
-                // I've used returned content area rectangle instead, expanding its width to
-                // response size.
+                if show_filter_row && viewer.is_filterable_column(col.0) {
+                    let mut filter = s.column_filter(col).to_owned();
+                    let resp = ui.add(
+                        egui::TextEdit::singleline(&mut filter)
+                            .hint_text(self.translator.translate("filter-row-hint"))
+                            .desired_width(f32::INFINITY),
+                    );
 
-                let drop_area_rect = rect.with_max_x(resp.rect.max.x);
-                let contains_pointer = ctx
-                    .pointer_hover_pos()
-                    .is_some_and(|pos| drop_area_rect.contains(pos));
+                    if resp.changed() {
+                        s.set_column_filter(col, filter);
+                    }
 
-                if !response_consumed && contains_pointer {
-                    if let Some(new_value) =
-                        viewer.on_cell_view_response(&table.rows[row_id.0], col.0, &resp)
-                    {
-                        let mut values = vec![(row_id, *col, RowSlabIndex(0))];
+                    if resp.has_focus() {
+                        // Keep table hotkeys from consuming the editor's input.
+                        s.cci_has_focus = false;
+                    }
+                }
 
-                        values.retain(|(row, col, _slab_id)| {
-                            viewer.is_editable_cell(col.0, row.0, &table.rows[row.0])
-                        });
+                painter = Some(ui.painter().clone());
+            });
 
-                        commands.push(Command::SetCells {
-                            slab: vec![*new_value].into_boxed_slice(),
-                            values: values.into_boxed_slice(),
-                        });
+            frame.content_widths[vis_col.0] = frame.content_widths[vis_col.0].max(content_width);
+
+            // Set drag payload for column reordering.
+            resp.dnd_set_drag_payload(vis_col);
+
+            if resp.dragged() {
+                Tooltip::always_open(ctx.clone(), frame.layer_id, "_EGUI_DATATABLE__COLUMN_MOVE__".into(), PopupAnchor::Pointer)
+                    .gap(12.0)
+                    .show(|ui|{
+                        let colum_name = viewer.column_name(col.0);
+                        ui.label(colum_name);
+                    });
+            }
+
+            if resp.hovered() && viewer.is_sortable_column(col.0)
+                && let Some(p) = &painter
+            {
+                p.rect_filled(
+                    col_rect,
+                    egui::CornerRadius::ZERO,
+                    visual.selection.bg_fill.gamma_multiply(0.2),
+                );
+            }
+
+            if viewer.is_sortable_column(col.0) && resp.clicked_by(PointerButton::Primary) {
+                let mut sort = s.sort().to_owned();
+                match sort.iter_mut().find(|(c, ..)| c == &col) {
+                    Some((_, asc)) => match asc.0 {
+                        true => asc.0 = false,
+                        false => sort.retain(|(c, ..)| c != &col),
+                    },
+                    None => {
+                        sort.push((col, IsAscending(true)));
                     }
                 }
-            }
 
-            /* -------------------------------- Editor Rendering -------------------------------- */
-            if let Some((should_focus, vis_column)) = edit_state {
-                let column = s.vis_cols()[vis_column.0];
-
-                egui::Window::new("")
-                    .id(ui_id.with(row_id).with(column))
-                    .constrain_to(body_max_rect)
-                    .fixed_pos(editing_cell_rect.min)
-                    .auto_sized()
-                    .min_size(editing_cell_rect.size())
-                    .max_width(editing_cell_rect.width())
-                    .title_bar(false)
-                    .frame(egui::Frame::NONE.corner_radius(egui::CornerRadius::same(3)))
-                    .show(ctx, |ui| {
-                        ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
-                            if let Some(resp) =
-                                viewer.show_cell_editor(ui, s.unwrap_editing_row_data(), column.0)
-                            {
-                                if should_focus {
-                                    resp.request_focus()
-                                }
+                commands.push(Command::SetColumnSort(sort));
+            }
 
-                                new_maximum_height = resp.rect.height().max(new_maximum_height);
-                            } else {
-                                commands.push(Command::CcCommitEdit);
-                            }
-                        });
-                    });
+            if resp.dnd_hover_payload::<VisColumnPos>().is_some()
+                && let Some(p) = &painter
+            {
+                p.rect_filled(
+                    col_rect,
+                    egui::CornerRadius::ZERO,
+                    visual.selection.bg_fill.gamma_multiply(0.5),
+                );
             }
 
-            // Accumulate response
-            if let Some(resp) = &mut resp_total {
-                *resp = resp.union(row.response());
-            } else {
-                resp_total = Some(row.response());
+            if let Some(payload) = resp.dnd_release_payload::<VisColumnPos>() {
+                commands.push(Command::CcReorderColumn {
+                    from: *payload,
+                    to: vis_col
+                        .0
+                        .pipe(|v| v + (payload.0 < v) as usize)
+                        .pipe(VisColumnPos),
+                })
             }
 
-            // Update row height cache if necessary.
-            if self.style.table_row_height.is_none() && prev_row_height != new_maximum_height {
-                row_height_updates.push((vis_row, new_maximum_height));
+            resp.context_menu(|ui| {
+                if ui.button(self.translator.translate("context-menu-hide")).clicked() {
+                    commands.push(Command::CcHideColumn(col));
+                }
+
+                if vis_col.0 + 1 < s.vis_cols().len()
+                    && ui.button(self.translator.translate("context-menu-auto-fit")).clicked()
+                {
+                    frame.auto_fit = Some(vis_col);
+                }
+
+                if !s.sort().is_empty() && ui.button(self.translator.translate("context-menu-clear-sort")).clicked() {
+                    commands.push(Command::SetColumnSort(Vec::new()));
+                }
+
+                if s.has_column_filters() && ui.button(self.translator.translate("context-menu-clear-filters")).clicked() {
+                    s.clear_column_filters();
+                }
+
+                if vis_col.0 + 1 < s.vis_cols().len()
+                    && vis_col.0 + 1 != s.frozen_columns()
+                    && ui.button(self.translator.translate("context-menu-freeze-columns")).clicked()
+                {
+                    s.set_frozen_columns(vis_col.0 + 1);
+                }
+
+                if s.frozen_columns() > 0 && ui.button(self.translator.translate("context-menu-unfreeze-columns")).clicked() {
+                    s.set_frozen_columns(0);
+                }
+
+                if s.group_by() != Some(col)
+                    && viewer.is_groupable_column(col.0)
+                    && ui.button(self.translator.translate("context-menu-group-by")).clicked()
+                {
+                    s.set_group_by(Some(col));
+                }
+
+                if s.group_by().is_some() && ui.button(self.translator.translate("context-menu-ungroup")).clicked() {
+                    s.set_group_by(None);
+                }
+
+                let custom_items = viewer.column_header_context_menu_items(col.0);
+                if let Some(id) = show_custom_menu_items(ui, custom_items, &frame.hotkeys) {
+                    frame
+                        .custom_actions
+                        .push((id, CustomActionOrigin::ColumnHeader(col.0)));
+                }
+
+                if has_any_hidden_col {
+                    ui.separator();
+                    ui.label(self.translator.translate("context-menu-hidden"));
+
+                    for col in (0..s.num_columns()).map(ColumnIdx) {
+                        if !s.vis_cols().contains(&col)
+                            && ui.button(viewer.column_name(col.0)).clicked()
+                        {
+                            commands.push(Command::CcShowColumn {
+                                what: col,
+                                at: vis_col,
+                            });
+                        }
+                    }
+                }
+            });
+        }
+
+        // Account for header response to calculate total response.
+        frame.accumulate_response(h.response());
+    }
+
+    fn show_pane_body(
+        &mut self,
+        body: egui_extras::TableBody<'_>,
+        group: &PaneGroup,
+        pane: &Pane,
+        row_heights: &[f32],
+        frame: &mut FrameState<R>,
+    ) {
+        let body_max_rect = body.max_rect();
+        let table_row_height = self.style.table_row_height;
+        let render_fn = |row: egui_extras::TableRow| {
+            let index = pane.rows.start + row.index();
+
+            match self.state.as_ref().unwrap().display_row(index) {
+                DisplayRow::Group(index) => self.show_group_header(row, index, group, frame),
+                DisplayRow::Row(vis_row) => {
+                    self.show_row(row, vis_row, group, pane, body_max_rect, frame)
+                }
             }
-        }; // ~ render_fn
+        };
 
         // Actual rendering
-        if let Some(height) = self.style.table_row_height {
-            body.rows(height, cc_row_heights.len(), render_fn);
+        if let Some(height) = table_row_height {
+            body.rows(height, pane.rows.len(), render_fn);
         } else {
-            body.heterogeneous_rows(cc_row_heights.iter().cloned(), render_fn);
+            body.heterogeneous_rows(row_heights[pane.rows.clone()].iter().cloned(), render_fn);
+        }
+    }
+
+    fn show_group_header(
+        &mut self,
+        mut row: egui_extras::TableRow,
+        index: usize,
+        group: &PaneGroup,
+        frame: &mut FrameState<R>,
+    ) {
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let table = &*self.table;
+        let row_group = s.group(index);
+        let mut toggled = false;
+
+        if group.row_header {
+            row.col(|ui| {
+                ui.horizontal(|ui| {
+                    let (icon, key) = match row_group.is_collapsed {
+                        true => ("⏵", "group-expand"),
+                        false => ("⏷", "group-collapse"),
+                    };
+
+                    toggled = ui
+                        .add(egui::Button::new(icon).small().frame(false))
+                        .on_hover_text(self.translator.translate(key))
+                        .clicked();
+
+                    viewer.show_group_header(
+                        ui,
+                        &RowGroup {
+                            key: &row_group.key,
+                            is_collapsed: row_group.is_collapsed,
+                            table: &table.rows,
+                            row_ids: &row_group.rows,
+                        },
+                    );
+                });
+            });
+        }
+
+        for vis_col in group.cols.clone() {
+            row.col(|ui| {
+                if let Some(Some(text)) = row_group.aggregates.get(vis_col) {
+                    ui.strong(text);
+                }
+            });
+        }
+
+        frame.accumulate_response(row.response());
+
+        if toggled {
+            s.toggle_group_collapsed(index);
+        }
+    }
+
+    fn show_row(
+        &mut self,
+        mut row: egui_extras::TableRow,
+        vis_row: VisRowPos,
+        group: &PaneGroup,
+        pane: &Pane,
+        body_max_rect: Rect,
+        frame: &mut FrameState<R>,
+    ) {
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let table = &mut *self.table;
+        let ctx = &frame.ctx;
+        let ui_id = frame.ui_id;
+        let visual = &frame.style.visuals;
+        let visible_cols = s.vis_cols().clone();
+        let no_rounding = egui::CornerRadius::ZERO;
+        let commands = &mut frame.commands;
+        let actions = &mut frame.actions;
+        let custom_actions = &mut frame.custom_actions;
+        let hotkeys = &frame.hotkeys;
+
+        if group.row_header && pane.scroll {
+            s.cci_page_row_count += 1;
+        }
+
+        let row_id = s.cc_rows[vis_row.0];
+
+        let mut row_elem_start = Default::default();
+
+        // Check if current row is edition target
+        let edit_state = s.row_editing_cell(row_id);
+        let mut editing_cell_rect = Rect::NOTHING;
+        let interactive_row = s.is_interactive_row(vis_row);
+        let rows_movable = s.can_reorder_rows() && viewer.allow_row_reordering();
+
+        let check_mouse_dragging_selection = {
+            let s_cci_has_focus = s.cci_has_focus;
+            let s_cci_has_selection = s.has_cci_selection();
+            let pointer_interact_pos = frame.pointer_interact_pos;
+            let pointer_primary_down = frame.pointer_primary_down;
+
+            move |rect: &Rect, resp: &egui::Response| {
+                let cci_hovered: bool = s_cci_has_focus
+                    && s_cci_has_selection
+                    && rect
+                        .with_max_x(resp.rect.right())
+                        .contains(pointer_interact_pos);
+                let sel_drag = cci_hovered && pointer_primary_down;
+                let sel_click = !s_cci_has_selection && resp.hovered() && pointer_primary_down;
+
+                sel_drag || sel_click
+            }
+        };
+
+        /* -------------------------------- Header Rendering -------------------------------- */
+
+        // Mark row background filled if being edited.
+        row.set_selected(edit_state.is_some());
+
+        // Render row header button
+        let head_resp = group.row_header.then(|| {
+            let (head_rect, head_resp) = row.col(|ui| {
+                // Calculate the position where values start.
+                row_elem_start = ui.max_rect().right_top();
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.separator();
+
+                    if frame.has_any_sort {
+                        ui.monospace(
+                            RichText::from(f!(
+                                "{:·>width$}",
+                                row_id.0,
+                                width = frame.row_id_digits as usize
+                            ))
+                            .strong(),
+                        );
+                    } else {
+                        ui.monospace(
+                            RichText::from(f!("{:>width$}", "", width = frame.row_id_digits as usize))
+                                .strong(),
+                        );
+                    }
+
+                    ui.monospace(
+                        RichText::from(f!(
+                            "{:·>width$}",
+                            vis_row.0 + 1,
+                            width = frame.vis_row_digits as usize
+                        ))
+                        .weak(),
+                    );
+                });
+            });
+
+            if rows_movable && !s.has_cci_selection() && s.is_row_selected(vis_row) {
+                // Dragging header of a selected row moves the selected rows, instead of
+                // selecting rows.
+                head_resp.dnd_set_drag_payload(RowMovePayload(ui_id));
+            } else if check_mouse_dragging_selection(&head_rect, &head_resp) {
+                s.cci_sel_update_row(vis_row);
+            }
+
+            if rows_movable {
+                let after = frame.pointer_interact_pos.y > head_rect.center().y;
+
+                if head_resp
+                    .dnd_hover_payload::<RowMovePayload>()
+                    .is_some_and(|x| x.0 == ui_id)
+                {
+                    let y = if after { head_rect.bottom() } else { head_rect.top() };
+                    ctx.layer_painter(frame.layer_id)
+                        .with_clip_rect(body_max_rect)
+                        .hline(
+                            head_rect.left()..=body_max_rect.right(),
+                            y,
+                            Stroke::new(2., visual.selection.bg_fill),
+                        );
+                }
+
+                if head_resp
+                    .dnd_release_payload::<RowMovePayload>()
+                    .is_some_and(|x| x.0 == ui_id)
+                {
+                    commands.extend(s.move_selected_rows(vis_row, after));
+                }
+            }
+
+            if head_resp.secondary_clicked() {
+                s.cci_row_header_menu = true;
+            }
+
+            head_resp
+        });
+
+        /* -------------------------------- Columns Rendering ------------------------------- */
+
+        // Overridable maximum height
+        let mut new_maximum_height = 0.;
+
+        // Fill handle is drawn on the bottom-right cell of the selection.
+        let is_filling = s.is_filling();
+        let fill_corner = s
+            .fill_source()
+            .filter(|_| !s.is_editing())
+            .map(|sel| sel.1.row_col(visible_cols.len()));
+
+        // Render cell contents regardless of the edition state.
+        for (vis_col, col) in visible_cols.iter().enumerate() {
+            if !group.cols.contains(&vis_col) {
+                continue;
+            }
+
+            let vis_col = VisColumnPos(vis_col);
+            let linear_index = vis_row.linear_index(visible_cols.len(), vis_col);
+            let selected = s.is_selected(vis_row, vis_col);
+            let cci_selected = s.is_selected_cci(vis_row, vis_col);
+            let is_editing = edit_state.is_some();
+            let is_interactive_cell = interactive_row.is_some_and(|x| x == vis_col);
+            let mut response_consumed = s.is_editing();
+            let is_fill_target = is_filling && s.is_fill_target(vis_row, vis_col);
+            let has_fill_handle = fill_corner == Some((vis_row, vis_col));
+            let mut on_fill_handle = false;
+            let mut fill_started = false;
+            let mut cell_style = viewer.cell_style(&table.rows[row_id.0], col.0);
+
+            // Validation result takes over the badge, prepending its message to the tooltip.
+            let validation = viewer.validate_cell(&table.rows[row_id.0], col.0);
+            if let Some(msg) = validation.message() {
+                cell_style.badge = Some(match validation.is_error() {
+                    true => visual.error_fg_color,
+                    false => visual.warn_fg_color,
+                });
+                cell_style.tooltip = Some(match cell_style.tooltip.take() {
+                    Some(tooltip) => f!("{msg}\n{tooltip}"),
+                    None => msg.to_owned(),
+                });
+            }
+
+            let (rect, resp) = row.col(|ui| {
+                let ui_max_rect = ui.max_rect();
+
+                if let Some(bg) = cell_style.bg {
+                    ui.painter().rect_filled(ui_max_rect.expand(1.), no_rounding, bg);
+                }
+
+                if cci_selected {
+                    ui.painter().rect_stroke(
+                        ui_max_rect,
+                        no_rounding,
+                        Stroke {
+                            width: 2.,
+                            color: self
+                                .style
+                                .fg_drag_selection
+                                .unwrap_or(visual.selection.bg_fill),
+                        },
+                        StrokeKind::Inside,
+                    );
+                }
+
+                if is_interactive_cell {
+                    ui.painter().rect_filled(
+                        ui_max_rect.expand(2.),
+                        no_rounding,
+                        self.style
+                            .bg_selected_highlight_cell
+                            .unwrap_or(visual.selection.bg_fill),
+                    );
+                } else if selected {
+                    ui.painter().rect_filled(
+                        ui_max_rect.expand(1.),
+                        no_rounding,
+                        self.style
+                            .bg_selected_cell
+                            .unwrap_or(visual.selection.bg_fill.gamma_multiply(0.5)),
+                    );
+                }
+
+                // Actual widget rendering happens within this line.
+
+                // ui.set_enabled(false);
+                ui.style_mut()
+                    .visuals
+                    .widgets
+                    .noninteractive
+                    .fg_stroke
+                    .color = if is_interactive_cell {
+                    self.style
+                        .fg_selected_highlight_cell
+                        .or(cell_style.fg)
+                        .unwrap_or(visual.strong_text_color())
+                } else {
+                    cell_style.fg.unwrap_or(visual.strong_text_color())
+                };
+
+                // FIXME: After egui 0.27, now the widgets spawned inside this closure
+                // intercepts interactions, which is basically natural behavior(Upper layer
+                // widgets). However, this change breaks current implementation which relies on
+                // the previous table behavior.
+                ui.add_enabled_ui(true, |ui| {
+                    if !(is_editing && is_interactive_cell) {
+                        viewer.show_cell_view(ui, &table.rows[row_id.0], col.0);
+                    }
+                });
+
+                #[cfg(any())]
+                if selected {
+                    ui.painter().rect_stroke(
+                        ui_max_rect,
+                        no_rounding,
+                        Stroke {
+                            width: 1.,
+                            color: visual.weak_text_color(),
+                        },
+                    );
+                }
+
+                if interactive_row.is_some() && !is_editing {
+                    let st = Stroke {
+                        width: 1.,
+                        color: self
+                            .style
+                            .focused_row_stroke
+                            .unwrap_or(visual.warn_fg_color.gamma_multiply(0.5)),
+                    };
+
+                    let xr = ui_max_rect.x_range();
+                    let yr = ui_max_rect.y_range();
+                    ui.painter().hline(xr, yr.min, st);
+                    ui.painter().hline(xr, yr.max, st);
+                }
+
+                if let Some(border) = cell_style.border {
+                    ui.painter()
+                        .rect_stroke(ui_max_rect, no_rounding, border, StrokeKind::Inside);
+                }
+
+                if let Some(badge) = cell_style.badge {
+                    let rt = ui_max_rect.right_top();
+                    ui.painter().add(egui::Shape::convex_polygon(
+                        vec![rt, rt + egui::vec2(-6., 0.), rt + egui::vec2(0., 6.)],
+                        badge,
+                        Stroke::NONE,
+                    ));
+                }
+
+                let fill_color = self
+                    .style
+                    .fg_drag_selection
+                    .unwrap_or(visual.selection.bg_fill);
+
+                if is_fill_target {
+                    ui.painter().rect_stroke(
+                        ui_max_rect,
+                        no_rounding,
+                        Stroke::new(1., fill_color),
+                        StrokeKind::Inside,
+                    );
+                }
+
+                if has_fill_handle {
+                    let handle = Rect::from_center_size(
+                        ui_max_rect.right_bottom() - egui::vec2(3., 3.),
+                        egui::vec2(6., 6.),
+                    );
+                    let handle_resp = ui
+                        .interact(handle, ui_id.with("fill-handle"), egui::Sense::drag())
+                        .on_hover_cursor(egui::CursorIcon::Crosshair);
+
+                    ui.painter().rect_filled(handle, no_rounding, fill_color);
+
+                    on_fill_handle = handle_resp.contains_pointer();
+                    fill_started = handle_resp.drag_started_by(PointerButton::Primary);
+                }
+
+                if edit_state.is_some_and(|(_, vis)| vis == vis_col) {
+                    editing_cell_rect = ui_max_rect;
+                }
+            });
+
+            let resp = match cell_style.tooltip {
+                Some(text) if !(is_editing && is_interactive_cell) => resp.on_hover_text(text),
+                _ => resp,
+            };
+
+            new_maximum_height = rect.height().max(new_maximum_height);
+            frame.content_widths[vis_col.0] = frame.content_widths[vis_col.0].max(rect.width());
+
+            if resp.secondary_clicked() {
+                s.cci_row_header_menu = false;
+            }
+
+            // -- Mouse Actions --
+            if fill_started {
+                response_consumed = true;
+                s.begin_fill();
+            } else if is_filling {
+                // Fill handle takes over dragging, instead of extending the selection.
+                if rect
+                    .with_max_x(resp.rect.right())
+                    .contains(frame.pointer_interact_pos)
+                {
+                    s.update_fill_target(vis_row, vis_col);
+                }
+            } else if !on_fill_handle && check_mouse_dragging_selection(&rect, &resp) {
+                // Expand cci selection
+                response_consumed = true;
+                s.cci_sel_update(linear_index);
+            }
+
+            let editable = viewer.is_editable_cell(vis_col.0, vis_row.0, &table.rows[row_id.0]);
+
+            if editable
+                && !on_fill_handle
+                && (resp.clicked_by(PointerButton::Primary)
+                    && (self.style.single_click_edit_mode || is_interactive_cell))
+            {
+                response_consumed = true;
+                commands.push(Command::CcEditStart(
+                    row_id,
+                    vis_col,
+                    viewer.clone_row(&table.rows[row_id.0]).into(),
+                ));
+                frame.edit_started = true;
+            }
+
+            /* --------------------------- Context Menu Rendering --------------------------- */
+
+            let menu_resp = match &head_resp {
+                Some(head_resp) => resp.clone() | head_resp.clone(),
+                None => resp.clone(),
+            };
+
+            menu_resp.context_menu(|ui| {
+                response_consumed = true;
+                ui.set_min_size(egui::vec2(250., 10.));
+
+                if !selected {
+                    commands.push(Command::CcSetSelection(vec![VisSelection(
+                        linear_index,
+                        linear_index,
+                    )]));
+                } else if !is_interactive_cell {
+                    s.set_interactive_cell(vis_row, vis_col);
+                }
+
+                let sel_multi_row = s.cursor_as_selection().is_some_and(|sel| {
+                    let mut min = usize::MAX;
+                    let mut max = usize::MIN;
+
+                    for sel in sel {
+                        min = min.min(sel.0 .0);
+                        max = max.max(sel.1 .0);
+                    }
+
+                    let (r_min, _) = VisLinearIdx(min).row_col(s.vis_cols().len());
+                    let (r_max, _) = VisLinearIdx(max).row_col(s.vis_cols().len());
+
+                    r_min != r_max
+                });
+
+                let cursor_x = ui.cursor().min.x;
+                let clip = s.has_clipboard_contents();
+                let b_undo = s.has_undo();
+                let b_redo = s.has_redo();
+                let mut n_sep_menu = 0;
+                let mut draw_sep = false;
+
+                let context_menu_items = [
+                    Some((selected, "🖻", "context-menu-selection-copy", UiAction::CopySelection)),
+                    Some((selected, "🖻", "context-menu-selection-cut", UiAction::CutSelection)),
+                    Some((selected, "🗙", "context-menu-selection-clear", UiAction::DeleteSelection)),
+                    Some((
+                        sel_multi_row,
+                        "🗐",
+                        "context-menu-selection-fill",
+                        UiAction::SelectionDuplicateValues,
+                    )),
+                    None,
+                    Some((clip, "➿", "context-menu-clipboard-paste", UiAction::PasteInPlace)),
+                    Some((
+                        clip && viewer.allow_row_insertions(),
+                        "🛠",
+                        "context-menu-clipboard-insert",
+                        UiAction::PasteInsert,
+                    )),
+                    None,
+                    Some((
+                        viewer.allow_row_insertions(),
+                        "🗐",
+                        "context-menu-row-duplicate",
+                        UiAction::DuplicateRow,
+                    )),
+                    Some((
+                        viewer.allow_row_deletions(),
+                        "🗙",
+                        "context-menu-row-delete",
+                        UiAction::DeleteRow,
+                    )),
+                    None,
+                    Some((b_undo, "⎗", "context-menu-undo", UiAction::Undo)),
+                    Some((b_redo, "⎘", "context-menu-redo", UiAction::Redo)),
+                ];
+
+                // Render built-in items
+                for opt in context_menu_items {
+                    if let Some((icon, key, action)) =
+                        opt.filter(|x| x.0).map(|x| (x.1, x.2, x.3))
+                    {
+                        if draw_sep {
+                            draw_sep = false;
+                            ui.separator();
+                        }
+
+                        let hotkey = hotkeys
+                            .iter()
+                            .find_map(|(k, a)| (a == &action).then(|| ctx.format_shortcut(k)));
+
+                        ui.horizontal(|ui| {
+                            ui.monospace(icon);
+                            ui.add_space(cursor_x + 20. - ui.cursor().min.x);
+
+                            let label = self.translator.translate(key);
+                            let btn = egui::Button::new(label)
+                                .shortcut_text(hotkey.unwrap_or_else(|| "🗙".into()));
+                            let r = ui.centered_and_justified(|ui| ui.add(btn)).inner;
+
+                            if r.clicked() {
+                                actions.push(action);
+                            }
+                        });
+
+                        n_sep_menu += 1;
+                    } else if n_sep_menu > 0 {
+                        n_sep_menu = 0;
+                        draw_sep = true;
+                    }
+                }
+
+                // Frozen rows
+                ui.separator();
+
+                let display_index = s.display_index(vis_row);
+
+                if display_index + 1 != s.frozen_rows()
+                    && ui.button(self.translator.translate("context-menu-freeze-rows")).clicked()
+                {
+                    s.set_frozen_rows(display_index + 1);
+                }
+
+                if s.frozen_rows() > 0
+                    && ui.button(self.translator.translate("context-menu-unfreeze-rows")).clicked()
+                {
+                    s.set_frozen_rows(0);
+                }
+
+                // Render custom items contributed by the viewer
+                let ui_ctx = s.ui_action_context();
+                let selection_snapshot = s.selection_snapshot(table);
+                // origin_cell is passed during dispatch from state; nothing to do here.
+
+                let custom_items = viewer.custom_context_menu_items(&ui_ctx, &selection_snapshot);
+                if let Some(id) = show_custom_menu_items(ui, custom_items, hotkeys) {
+                    actions.push(UiAction::Custom(id));
+                }
+
+                if s.cci_row_header_menu {
+                    let row_items =
+                        viewer.row_header_context_menu_items(row_id.0, &table.rows[row_id.0]);
+                    if let Some(id) = show_custom_menu_items(ui, row_items, hotkeys) {
+                        custom_actions.push((id, CustomActionOrigin::RowHeader(row_id.0)));
+                    }
+                }
+            });
+
+            // Forward DnD event if not any event was consumed by the response.
+
+            // FIXME: Upgrading egui 0.29 make interaction rectangle of response object
+            // larger(in y axis) than actually visible column cell size. To deal with this,
+            // I've used returned content area rectangle instead, expanding its width to
+            // response size.
+
+            let drop_area_rect = rect.with_max_x(resp.rect.max.x);
+            let contains_pointer = ctx
+                .pointer_hover_pos()
+                .is_some_and(|pos| drop_area_rect.contains(pos));
+
+            if !response_consumed
+                && contains_pointer
+                && let Some(new_value) =
+                    viewer.on_cell_view_response(&table.rows[row_id.0], col.0, &resp)
+            {
+                let mut values = vec![(row_id, *col, RowSlabIndex(0))];
+
+                values.retain(|(row, col, _slab_id)| {
+                    viewer.is_editable_cell(col.0, row.0, &table.rows[row.0])
+                });
+
+                commands.push(Command::SetCells {
+                    slab: vec![*new_value].into_boxed_slice(),
+                    values: values.into_boxed_slice(),
+                });
+            }
         }
 
+        /* -------------------------------- Editor Rendering -------------------------------- */
+        if let Some((should_focus, vis_column)) = edit_state
+            && group.cols.contains(&vis_column.0)
+        {
+            let column = s.vis_cols()[vis_column.0];
+
+            egui::Window::new("")
+                .id(ui_id.with(row_id).with(column))
+                .constrain_to(body_max_rect)
+                .fixed_pos(editing_cell_rect.min)
+                .auto_sized()
+                .min_size(editing_cell_rect.size())
+                .max_width(editing_cell_rect.width())
+                .title_bar(false)
+                .frame(egui::Frame::NONE.corner_radius(egui::CornerRadius::same(3)))
+                .show(ctx, |ui| {
+                    ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
+                        if let Some(resp) =
+                            viewer.show_cell_editor(ui, s.unwrap_editing_row_data(), column.0)
+                        {
+                            if should_focus {
+                                resp.request_focus()
+                            }
+
+                            new_maximum_height = resp.rect.height().max(new_maximum_height);
+
+                            // Validate the value being edited, before it gets committed.
+                            let validation =
+                                viewer.validate_cell(s.unwrap_editing_row_data(), column.0);
+                            if let Some(msg) = validation.message() {
+                                let color = match validation.is_error() {
+                                    true => visual.error_fg_color,
+                                    false => visual.warn_fg_color,
+                                };
+
+                                ui.painter().rect_stroke(
+                                    resp.rect,
+                                    no_rounding,
+                                    Stroke { width: 1., color },
+                                    StrokeKind::Outside,
+                                );
+                                resp.on_hover_text(msg);
+                            }
+                        } else {
+                            commands.push(Command::CcCommitEdit);
+                        }
+                    });
+                });
+        }
+
+        // Accumulate response
+        frame.accumulate_response(row.response());
+
+        // Row height is the largest one among every pane which renders the row.
+        let (height, num_measured) = frame
+            .measured_row_heights
+            .entry(vis_row)
+            .or_insert((0., 0));
+        *height = new_maximum_height.max(*height);
+        *num_measured += 1;
+    }
+
+    /// Handles events and commands collected while rendering panes.
+    fn end_frame(
+        &mut self,
+        frame: FrameState<R>,
+        mut cc_row_heights: Vec<f32>,
+        num_col_groups: usize,
+    ) -> Response {
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let table = &mut *self.table;
+        let FrameState {
+            mut commands,
+            actions,
+            custom_actions,
+            column_widths,
+            is_resizing,
+            content_widths,
+            auto_fit,
+            edit_started,
+            resp_total,
+            measured_row_heights,
+            pointer_interact_pos,
+            ctx,
+            ..
+        } = frame;
+
         /* ----------------------------------- Event Handling ----------------------------------- */
 
         if ctx.input(|i| i.pointer.button_released(PointerButton::Primary)) {
             let mods = ctx.input(|i| i.modifiers);
-            if let Some(sel) = s.cci_take_selection(mods).filter(|_| !edit_started) {
+            let cci_sel = s.cci_take_selection(mods);
+
+            if s.is_filling() {
+                commands.extend(s.finish_fill(table, viewer));
+            } else if let Some(sel) = cci_sel.filter(|_| !edit_started) {
                 commands.push(Command::CcSetSelection(sel));
             }
+        } else if s.is_filling() {
+            ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
         }
 
         // Control overall focus status.
@@ -913,23 +1742,78 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             }
         }
 
-        // Check in borrowed `cc_rows` back to state.
-        s.cc_row_heights = cc_row_heights.tap_mut(|values| {
-            if !row_height_updates.is_empty() {
-                ctx.request_repaint();
+        // Column widths are stored when user finishes resizing, or auto-fits a column.
+        let num_vis_cols = s.vis_cols().len();
+        let rendered_widths = (0..num_vis_cols.saturating_sub(1))
+            .filter(|c| column_widths[*c] > 0.)
+            .map(|c| (s.vis_cols()[c], column_widths[c]))
+            .collect::<Vec<_>>();
+
+        if let Some(vis_col) = auto_fit.filter(|c| content_widths[c.0] > 0.) {
+            let col = s.vis_cols()[vis_col.0];
+            let fit_width = (col, content_widths[vis_col.0]);
+            s.set_column_widths(rendered_widths.into_iter().chain([fit_width]));
+            s.cci_reset_column_widths = true;
+            ctx.request_repaint();
+        } else if !is_resizing && s.cci_resizing_columns {
+            s.set_column_widths(rendered_widths);
+        }
+
+        s.cci_resizing_columns = is_resizing;
+
+        // Source-backed tables fetch the rendered rows on the next frame.
+        let rendered_rows = measured_row_heights
+            .keys()
+            .filter_map(|vis_row| s.cc_rows.get(vis_row.0))
+            .map(|row| row.0)
+            .minmax()
+            .into_option()
+            .map_or(0..0, |(first, last)| first..last + 1);
+
+        if s.set_rendered_rows(rendered_rows) && s.is_source_backed() {
+            ctx.request_repaint();
+        }
+
+        // Update row height cache if necessary. Rows which are not rendered by every column
+        // group yet are skipped, as their heights are only partially measured.
+        if self.style.table_row_height.is_none() {
+            let mut any_update = false;
+
+            for (vis_row, (height, num_measured)) in measured_row_heights {
+                if num_measured >= num_col_groups && cc_row_heights[vis_row.0] != height {
+                    cc_row_heights[vis_row.0] = height;
+                    any_update = true;
+                }
             }
 
-            for (row_index, row_height) in row_height_updates {
-                values[row_index.0] = row_height;
+            if any_update {
+                ctx.request_repaint();
             }
-        });
+        }
+
+        // Check in borrowed `cc_rows` back to state.
+        s.cc_row_heights = cc_row_heights;
 
         // Handle queued actions
-        commands.extend(
-            actions
-                .into_iter()
-                .flat_map(|action| s.try_apply_ui_action(table, viewer, action)),
-        );
+        s.clipboard_format = self.style.clipboard_format;
+        s.undo_capacity = if self.style.max_undo_history == 0 {
+            100
+        } else {
+            self.style.max_undo_history
+        };
+
+        for action in actions {
+            // Commands from a single action are undone at once.
+            commands.push(Command::CcBeginUndoGroup);
+            commands.extend(s.try_apply_ui_action(table, viewer, action));
+            commands.push(Command::CcEndUndoGroup);
+        }
+
+        for (action_id, origin) in custom_actions {
+            commands.push(Command::CcBeginUndoGroup);
+            commands.extend(s.custom_action_commands(table, viewer, action_id, origin));
+            commands.push(Command::CcEndUndoGroup);
+        }
 
         // Handle queued commands
         for cmd in commands {
@@ -947,25 +1831,370 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                         });
                     }
 
-                    s.push_new_command(
-                        table,
-                        viewer,
-                        cmd,
-                        if self.style.max_undo_history == 0 {
-                            100
-                        } else {
-                            self.style.max_undo_history
-                        },
-                    );
+                    s.push_new_command(table, viewer, cmd, s.undo_capacity);
                 }
             }
         }
 
+        // Keeps polling the background rebuild, too.
+        if s.has_pending_source_sync() || s.is_rebuilding() {
+            ctx.request_repaint();
+        }
+
         // Total response
         resp_total.unwrap()
     }
 }
 
+/// Drag payload of row headers. Carries the table id, as payloads are shared between tables.
+#[derive(Clone, Copy)]
+struct RowMovePayload(egui::Id);
+
+/// Range of visible columns rendered together. When any column is frozen, the table is split
+/// into two groups; frozen columns on the left, and horizontally scrolling ones on the right.
+struct PaneGroup {
+    cols: std::ops::Range<usize>,
+
+    /// Whether the group renders the row header column.
+    row_header: bool,
+
+    /// Vertical scroll offset to follow, when there are multiple groups.
+    sync_offset: Option<f32>,
+}
+
+/// A part of a column group, rendered as a single table. When any row is frozen, each group is
+/// split into frozen rows on the top, and vertically scrolling rows on the bottom.
+struct Pane {
+    rows: std::ops::Range<usize>,
+
+    /// Whether the pane renders the column header row.
+    header: bool,
+
+    /// Whether the pane scrolls vertically.
+    scroll: bool,
+}
+
+/// Values collected while rendering every pane of a single frame.
+struct FrameState<R> {
+    commands: Vec<Command<R>>,
+    actions: Vec<UiAction>,
+
+    /// Custom actions invoked from header context menus.
+    custom_actions: Vec<(&'static str, CustomActionOrigin)>,
+
+    /// Discard widths remembered by table widgets, to apply stored column widths.
+    reset_column_widths: bool,
+
+    /// Rendered width of each visible column.
+    column_widths: Vec<f32>,
+
+    /// A column border is being dragged.
+    is_resizing: bool,
+
+    /// Widest content of each visible column, among rendered cells.
+    content_widths: Vec<f32>,
+
+    /// Column to fit to its contents at the end of the frame.
+    auto_fit: Option<VisColumnPos>,
+    hotkeys: Vec<(egui::KeyboardShortcut, UiAction)>,
+    edit_started: bool,
+    resp_total: Option<Response>,
+
+    /// Rendered row height, and number of panes which rendered the row.
+    measured_row_heights: ahash::HashMap<VisRowPos, (f32, usize)>,
+
+    /// Display position of the row to scroll to.
+    scroll_to_row: Option<usize>,
+    header_height: f32,
+    show_filter_row: bool,
+    green: Color32,
+    vis_row_digits: u32,
+    row_id_digits: u32,
+    has_any_sort: bool,
+    pointer_interact_pos: egui::Pos2,
+    pointer_primary_down: bool,
+    layer_id: egui::LayerId,
+    ui_id: egui::Id,
+    ctx: egui::Context,
+    style: Arc<egui::Style>,
+}
+
+impl<R> FrameState<R> {
+    fn accumulate_response(&mut self, resp: Response) {
+        self.resp_total = Some(match self.resp_total.take() {
+            Some(total) => total.union(resp),
+            None => resp,
+        });
+    }
+}
+
+type SortConfig = Vec<(ColumnIdx, IsAscending)>;
+
+/// Renders menu items contributed by the viewer after a separator, skipping disabled ones.
+/// Returns id of the clicked item.
+fn show_custom_menu_items(
+    ui: &mut egui::Ui,
+    items: Vec<CustomMenuItem>,
+    hotkeys: &[(egui::KeyboardShortcut, UiAction)],
+) -> Option<&'static str> {
+    if items.is_empty() {
+        return None;
+    }
+
+    ui.separator();
+    let mut clicked = None;
+    show_custom_menu_item_list(ui, items, hotkeys, &mut clicked);
+    clicked
+}
+
+fn show_custom_menu_item_list(
+    ui: &mut egui::Ui,
+    items: Vec<CustomMenuItem>,
+    hotkeys: &[(egui::KeyboardShortcut, UiAction)],
+    clicked: &mut Option<&'static str>,
+) {
+    for item in items {
+        if item.is_separator {
+            ui.separator();
+            continue;
+        }
+
+        if !item.enabled {
+            continue;
+        }
+
+        ui.horizontal(|ui| {
+            if let Some(icon) = item.icon {
+                ui.monospace(icon);
+            }
+
+            if !item.children.is_empty() {
+                ui.menu_button(item.label, |ui| {
+                    show_custom_menu_item_list(ui, item.children, hotkeys, clicked)
+                });
+                return;
+            }
+
+            let hotkey = hotkeys.iter().find_map(|(k, a)| {
+                (a == &UiAction::Custom(item.id)).then(|| ui.ctx().format_shortcut(k))
+            });
+
+            let mut btn = match item.checked {
+                Some(checked) => egui::Button::selectable(checked, item.label),
+                None => egui::Button::new(item.label),
+            };
+
+            if let Some(hotkey) = hotkey {
+                btn = btn.shortcut_text(hotkey);
+            }
+
+            if ui.centered_and_justified(|ui| ui.add(btn)).inner.clicked() {
+                *clicked = Some(item.id);
+            }
+        });
+    }
+}
+
+/// Renders contents of the find & replace overlay. Returns requested operation, and whether any
+/// of its editors has keyboard focus.
+fn show_find_overlay(
+    ui: &mut egui::Ui,
+    find: &mut FindState,
+    translator: &dyn Translator,
+) -> (Option<FindRequest>, bool) {
+    let mut request = None;
+    let mut has_focus = false;
+    let has_query = !find.query.is_empty();
+    let is_closing = |resp: &Response| {
+        resp.lost_focus() && resp.ctx.input(|i| i.key_pressed(egui::Key::Escape))
+    };
+
+    ui.horizontal(|ui| {
+        let resp = ui.add(
+            egui::TextEdit::singleline(&mut find.query)
+                .hint_text(translator.translate("find-hint"))
+                .desired_width(160.),
+        );
+
+        if replace(&mut find.want_focus, false) {
+            resp.request_focus();
+        }
+
+        if resp.changed() {
+            find.no_match = false;
+        }
+
+        if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            request = Some(match ui.input(|i| i.modifiers.shift) {
+                true => FindRequest::Previous,
+                false => FindRequest::Next,
+            });
+
+            // Keep the focus, to repeat search with Enter key.
+            find.want_focus = true;
+        }
+
+        find.is_open &= !is_closing(&resp);
+        has_focus |= resp.has_focus();
+
+        ui.toggle_value(&mut find.match_case, "Aa")
+            .on_hover_text(translator.translate("find-match-case"));
+
+        if ui
+            .add_enabled(has_query, egui::Button::new("⏶").small())
+            .on_hover_text(translator.translate("find-previous"))
+            .clicked()
+        {
+            request = Some(FindRequest::Previous);
+        }
+
+        if ui
+            .add_enabled(has_query, egui::Button::new("⏷").small())
+            .on_hover_text(translator.translate("find-next"))
+            .clicked()
+        {
+            request = Some(FindRequest::Next);
+        }
+
+        if ui
+            .small_button("🗙")
+            .on_hover_text(translator.translate("find-close"))
+            .clicked()
+        {
+            find.is_open = false;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let resp = ui.add(
+            egui::TextEdit::singleline(&mut find.replacement)
+                .hint_text(translator.translate("find-replace-hint"))
+                .desired_width(160.),
+        );
+
+        find.is_open &= !is_closing(&resp);
+        has_focus |= resp.has_focus();
+
+        if ui
+            .add_enabled(has_query, egui::Button::new(translator.translate("find-replace")))
+            .clicked()
+        {
+            request = Some(FindRequest::Replace);
+        }
+
+        if ui
+            .add_enabled(has_query, egui::Button::new(translator.translate("find-replace-all")))
+            .clicked()
+        {
+            request = Some(FindRequest::ReplaceAll);
+        }
+    });
+
+    if find.no_match {
+        ui.colored_label(ui.visuals().warn_fg_color, translator.translate("find-no-match"));
+    }
+
+    (request, has_focus)
+}
+
+/// Renders contents of the sort configuration popup. Returns new sort configuration if user
+/// changed anything.
+fn show_sort_config<R>(
+    ui: &mut egui::Ui,
+    viewer: &mut impl RowViewer<R>,
+    sort: &[(ColumnIdx, IsAscending)],
+    num_columns: usize,
+    translator: &dyn Translator,
+) -> Option<SortConfig> {
+    let mut new_sort = None::<SortConfig>;
+    let mut edit = |f: &mut dyn FnMut(&mut SortConfig)| {
+        f(new_sort.get_or_insert_with(|| sort.to_vec()))
+    };
+
+    ui.set_min_width(200.);
+    ui.strong(translator.translate("sort-config-title"));
+
+    if sort.is_empty() {
+        ui.weak(translator.translate("sort-config-empty"));
+    }
+
+    for (pos, (col, asc)) in sort.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.monospace(f!("{}.", pos + 1));
+            ui.label(viewer.column_name(col.0));
+
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui
+                    .small_button("🗙")
+                    .on_hover_text(translator.translate("sort-config-remove"))
+                    .clicked()
+                {
+                    edit(&mut |x| {
+                        x.remove(pos);
+                    });
+                }
+
+                if ui
+                    .add_enabled(pos + 1 < sort.len(), egui::Button::new("⏷").small())
+                    .on_hover_text(translator.translate("sort-config-move-down"))
+                    .clicked()
+                {
+                    edit(&mut |x| x.swap(pos, pos + 1));
+                }
+
+                if ui
+                    .add_enabled(pos > 0, egui::Button::new("⏶").small())
+                    .on_hover_text(translator.translate("sort-config-move-up"))
+                    .clicked()
+                {
+                    edit(&mut |x| x.swap(pos - 1, pos));
+                }
+
+                let (icon, key) = match asc.0 {
+                    true => ("↗", "sort-config-ascending"),
+                    false => ("↘", "sort-config-descending"),
+                };
+
+                if ui
+                    .small_button(icon)
+                    .on_hover_text(translator.translate(key))
+                    .clicked()
+                {
+                    edit(&mut |x| x[pos].1 .0 = !asc.0);
+                }
+            });
+        });
+    }
+
+    let addable = (0..num_columns)
+        .map(ColumnIdx)
+        .filter(|c| sort.iter().all(|(x, _)| x != c) && viewer.is_sortable_column(c.0))
+        .collect::<Vec<_>>();
+
+    if !addable.is_empty() {
+        ui.separator();
+        ui.label(translator.translate("sort-config-add"));
+
+        ui.horizontal_wrapped(|ui| {
+            for col in addable {
+                if ui.small_button(viewer.column_name(col.0)).clicked() {
+                    edit(&mut |x| x.push((col, IsAscending(true))));
+                }
+            }
+        });
+    }
+
+    if !sort.is_empty() {
+        ui.separator();
+
+        if ui.button(translator.translate("context-menu-clear-sort")).clicked() {
+            new_sort = Some(Vec::new());
+        }
+    }
+
+    new_sort
+}
+
 impl<R, V: RowViewer<R>> Drop for Renderer<'_, R, V> {
     fn drop(&mut self) {
         self.table.ui = self.state.take();
@@ -999,11 +2228,66 @@ impl Translator for EnglishTranslator {
             "context-menu-row-delete" => "Row: Delete",
             "context-menu-undo" => "Undo",
             "context-menu-redo" => "Redo",
+            "context-menu-freeze-rows" => "Freeze rows up to here",
+            "context-menu-unfreeze-rows" => "Unfreeze rows",
 
             // column header context menu
             "context-menu-hide" => "Hide",
+            "context-menu-auto-fit" => "Auto-fit width",
             "context-menu-hidden" => "Hidden",
             "context-menu-clear-sort" => "Clear sort",
+            "context-menu-clear-filters" => "Clear filters",
+            "context-menu-freeze-columns" => "Freeze columns up to here",
+            "context-menu-unfreeze-columns" => "Unfreeze columns",
+            "context-menu-group-by" => "Group by this column",
+            "context-menu-ungroup" => "Ungroup",
+
+            // footer
+            "footer-aggregates-rows" => "Aggregates of visible rows",
+            "footer-aggregates-selection" => "Aggregates of selected cells",
+            "status-bar-rows" => "Rows",
+            "status-bar-selected-cells" => "Selected cells",
+            "status-bar-selected-rows" => "Selected rows",
+
+            // row group header
+            "group-expand" => "Expand",
+            "group-collapse" => "Collapse",
+
+            // sort configuration popup
+            "sort-config-tooltip" => "Configure sorting",
+            "sort-config-title" => "Sort order",
+            "sort-config-empty" => "Not sorted",
+            "sort-config-add" => "Add sort column",
+            "sort-config-remove" => "Remove",
+            "sort-config-move-up" => "Move up",
+            "sort-config-move-down" => "Move down",
+            "sort-config-ascending" => "Ascending",
+            "sort-config-descending" => "Descending",
+            "sort-config-rebuilding" => "Sorting and filtering rows…",
+
+            // filter row
+            "filter-row-hint" => "Filter",
+            "filter-row-clear" => "Clear all filters",
+
+            // find & replace overlay
+            "find-hint" => "Find",
+            "find-replace-hint" => "Replace with",
+            "find-match-case" => "Match case",
+            "find-previous" => "Previous match",
+            "find-next" => "Next match",
+            "find-close" => "Close",
+            "find-replace" => "Replace",
+            "find-replace-all" => "Replace all",
+            "find-no-match" => "No match",
+
+            // undo history
+            "history-set-cells" => "Set cells",
+            "history-set-row" => "Edit row",
+            "history-insert-rows" => "Insert rows",
+            "history-remove-rows" => "Remove rows",
+            "history-move-rows" => "Move rows",
+            "history-set-column-sort" => "Sort columns",
+            "history-set-visible-columns" => "Change visible columns",
             _ => key,
         }.to_string()
     }
@@ -0,0 +1,356 @@
+#![allow(unused)]
+//! A short implementation for reading and writing RFC 4180 style CSV data. Delimiter is
+//! configurable, therefore this also covers the quoted TSV format used by spreadsheets.
+
+use std::ops::Range;
+
+pub fn write_delimiter(buf: &mut String, delimiter: char) {
+    buf.push(delimiter);
+}
+
+pub fn write_newline(buf: &mut String) {
+    buf.push('\n');
+}
+
+/// Writes the field, quoting it only if necessary.
+pub fn write_content(buf: &mut String, item: &str, delimiter: char) {
+    let need_quote = item
+        .chars()
+        .any(|ch| ch == delimiter || matches!(ch, '"' | '\n' | '\r'));
+
+    if need_quote {
+        write_quoted(buf, item);
+    } else {
+        buf.push_str(item);
+    }
+}
+
+/// Writes the field always wrapped with double quotes. Use this to express explicit empty
+/// data(`""`), which differs from an empty gap between two delimiters.
+pub fn write_quoted(buf: &mut String, item: &str) {
+    buf.reserve(item.len() + 2);
+    buf.push('"');
+
+    for char in item.chars() {
+        if char == '"' {
+            buf.push('"');
+        }
+
+        buf.push(char);
+    }
+
+    buf.push('"');
+}
+
+/* ============================================================================================== */
+/*                                             READER                                             */
+/* ============================================================================================== */
+
+pub struct ParsedCsv {
+    /// Unquoted contents of every cell.
+    data: String,
+
+    /// Byte span info for each cell in the data. Every delimited cell is stored even if it is
+    /// empty.
+    cell_spans: Vec<Range<u32>>,
+
+    /// Whether each cell was wrapped with double quotes.
+    cell_quoted: Vec<bool>,
+
+    /// Index offsets for start of each row in the `cell_spans` vector.
+    row_offsets: Vec<u32>,
+}
+
+impl ParsedCsv {
+    /// Parses the data strictly; returns `None` if any quoted field is malformed, i.e. not
+    /// closed, or followed by anything other than delimiter or newline.
+    pub fn parse(data: &str, delimiter: char) -> Option<Self> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum ParseState {
+            FieldStart,
+            Unquoted,
+            Quoted,
+            QuoteInQuoted,
+        }
+
+        let mut s = Self {
+            data: Default::default(),
+            cell_spans: Default::default(),
+            cell_quoted: Default::default(),
+            row_offsets: Default::default(),
+        };
+
+        let mut state = ParseState::FieldStart;
+        let mut cell_start_char = 0;
+        let mut is_quoted = false;
+
+        // Whether a delimiter was just consumed; which implies that another field follows.
+        let mut pending_field = false;
+
+        // Add initial row offset.
+        s.row_offsets.push(0);
+
+        macro_rules! push_cell {
+            () => {{
+                s.cell_spans.push(cell_start_char..s.data.len() as u32);
+                s.cell_quoted.push(is_quoted);
+                cell_start_char = s.data.len() as u32;
+                is_quoted = false;
+            }};
+        }
+
+        for char in data.chars() {
+            state = match (state, char) {
+                (ParseState::Quoted, '"') => ParseState::QuoteInQuoted,
+                (ParseState::Quoted, ch) => {
+                    s.data.push(ch);
+                    ParseState::Quoted
+                }
+                (ParseState::QuoteInQuoted, '"') => {
+                    s.data.push('"');
+                    ParseState::Quoted
+                }
+                (_, '\r') => {
+                    // Ignoring, outside of quotes.
+                    state
+                }
+                (_, ch) if ch == delimiter => {
+                    push_cell!();
+                    pending_field = true;
+                    ParseState::FieldStart
+                }
+                (state, '\n') => {
+                    if state != ParseState::FieldStart || pending_field {
+                        push_cell!();
+                    }
+
+                    // Add row offset and move to new row.
+                    s.row_offsets.push(s.cell_spans.len() as _);
+                    pending_field = false;
+                    ParseState::FieldStart
+                }
+                (ParseState::QuoteInQuoted, _) => {
+                    // Any content after closing quote is malformed.
+                    return None;
+                }
+                (ParseState::FieldStart, '"') => {
+                    is_quoted = true;
+                    pending_field = false;
+                    ParseState::Quoted
+                }
+                (ParseState::FieldStart | ParseState::Unquoted, ch) => {
+                    // Be lenient on quotes within unquoted field.
+                    s.data.push(ch);
+                    pending_field = false;
+                    ParseState::Unquoted
+                }
+            };
+        }
+
+        // Need to check if we have any remaining cell to add.
+        match state {
+            ParseState::Quoted => return None,
+            ParseState::Unquoted | ParseState::QuoteInQuoted => push_cell!(),
+            ParseState::FieldStart if pending_field => push_cell!(),
+            ParseState::FieldStart => {}
+        }
+
+        if *s.row_offsets.last().unwrap() != s.cell_spans.len() as u32 {
+            s.row_offsets.push(s.cell_spans.len() as _);
+        }
+
+        // Optimize buffer usage.
+        s.data.shrink_to_fit();
+        s.cell_spans.shrink_to_fit();
+        s.cell_quoted.shrink_to_fit();
+        s.row_offsets.shrink_to_fit();
+
+        Some(s)
+    }
+
+    /// Parses clipboard text, if it looks like quoted data; i.e. there's any field which starts
+    /// with double quote. Tab is preferred as delimiter if there's any, since it's what
+    /// spreadsheets use. Returns `None` for anything else, which should be regarded as escaped
+    /// TSV.
+    pub fn parse_detected(data: &str) -> Option<Self> {
+        let mut prev = '\n';
+        let mut any_quoted_field = false;
+        let mut has_tab = false;
+        let mut has_comma = false;
+
+        for char in data.chars() {
+            any_quoted_field |= char == '"' && matches!(prev, '\n' | '\t' | ',');
+            has_tab |= char == '\t';
+            has_comma |= char == ',';
+            prev = char;
+        }
+
+        if !any_quoted_field {
+            return None;
+        }
+
+        let tab = has_tab.then_some('\t');
+        let comma = has_comma.then_some(',');
+
+        [tab, comma, Some('\t')]
+            .into_iter()
+            .flatten()
+            .find_map(|delimiter| Self::parse(data, delimiter))
+    }
+
+    /// Calculate the width of the table. This is the longest row in the table.
+    pub fn calc_table_width(&self) -> usize {
+        self.row_offsets
+            .windows(2)
+            .map(|range| range[1] - range[0])
+            .max()
+            .unwrap_or(0) as usize
+    }
+
+    pub fn num_columns_at(&self, row: usize) -> usize {
+        if row >= self.row_offsets.len() - 1 {
+            return 0;
+        }
+
+        let start = self.row_offsets[row] as usize;
+        let end = self.row_offsets[row + 1] as usize;
+
+        end - start
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.row_offsets.len() - 1
+    }
+
+    pub fn get_cell(&self, row: usize, column: usize) -> Option<&str> {
+        let cell_offset = self.cell_offset(row, column)?;
+        let cell_span = &self.cell_spans[cell_offset];
+
+        Some(&self.data[cell_span.start as usize..cell_span.end as usize])
+    }
+
+    pub fn is_quoted(&self, row: usize, column: usize) -> bool {
+        self.cell_offset(row, column)
+            .is_some_and(|offset| self.cell_quoted[offset])
+    }
+
+    fn cell_offset(&self, row: usize, column: usize) -> Option<usize> {
+        (column < self.num_columns_at(row)).then(|| self.row_offsets[row] as usize + column)
+    }
+
+    /// Iterates (row, (column, cell data, is quoted)) tuples.
+    pub fn iter_rows(
+        &self,
+    ) -> impl Iterator<Item = (usize, impl Iterator<Item = (usize, &str, bool)>)> {
+        self.row_offsets
+            .windows(2)
+            .enumerate()
+            .map(move |(row, range)| {
+                let (start, end) = (range[0] as usize, range[1] as usize);
+                let row_iter = (start..end).map(move |cell_offset| {
+                    let cell_span = &self.cell_spans[cell_offset];
+                    (
+                        cell_offset - start,
+                        &self.data[cell_span.start as usize..cell_span.end as usize],
+                        self.cell_quoted[cell_offset],
+                    )
+                });
+
+                (row, row_iter)
+            })
+    }
+}
+
+#[test]
+fn csv_parsing() {
+    const CSV_DATA: &str = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",,\"\"\n\nlast";
+
+    let parsed = ParsedCsv::parse(CSV_DATA, ',').unwrap();
+    assert_eq!(parsed.num_rows(), 4);
+    assert_eq!(parsed.num_columns_at(0), 3);
+    assert_eq!(parsed.num_columns_at(1), 3);
+    assert_eq!(parsed.num_columns_at(2), 0);
+    assert_eq!(parsed.num_columns_at(3), 1);
+    assert_eq!(parsed.calc_table_width(), 3);
+
+    assert_eq!(parsed.get_cell(0, 0), Some("a"));
+    assert_eq!(parsed.get_cell(0, 1), Some("b,c"));
+    assert_eq!(parsed.get_cell(0, 2), Some("say \"hi\""));
+    assert_eq!(parsed.get_cell(1, 0), Some("multi\nline"));
+    assert_eq!(parsed.get_cell(1, 1), Some(""));
+    assert_eq!(parsed.get_cell(1, 2), Some(""));
+    assert_eq!(parsed.get_cell(3, 0), Some("last"));
+    assert!(parsed.get_cell(3, 1).is_none());
+
+    assert!(!parsed.is_quoted(1, 1));
+    assert!(parsed.is_quoted(1, 2));
+
+    // Round trip through the writer.
+    let mut buf = String::new();
+    for (row, cells) in parsed.iter_rows() {
+        if row > 0 {
+            write_newline(&mut buf);
+        }
+
+        for (col, data, quoted) in cells {
+            if col > 0 {
+                write_delimiter(&mut buf, '\t');
+            }
+
+            if quoted && data.is_empty() {
+                write_quoted(&mut buf, data);
+            } else {
+                write_content(&mut buf, data, '\t');
+            }
+        }
+    }
+
+    assert_eq!(buf, "a\tb,c\t\"say \"\"hi\"\"\"\n\"multi\nline\"\t\t\"\"\n\nlast");
+    assert_eq!(
+        ParsedCsv::parse(&buf, '\t').unwrap().get_cell(0, 2),
+        Some("say \"hi\"")
+    );
+
+    // Malformed data.
+    assert!(ParsedCsv::parse("\"unclosed", ',').is_none());
+    assert!(ParsedCsv::parse("\"closed\"trailing", ',').is_none());
+
+    // Format detection.
+    assert!(ParsedCsv::parse_detected("plain\tdata, with comma").is_none());
+    assert!(ParsedCsv::parse_detected("\"unclosed\tdata").is_none());
+
+    let detected = ParsedCsv::parse_detected("\"a\tb\",c").unwrap();
+    assert_eq!(detected.get_cell(0, 0), Some("a\tb"));
+    assert_eq!(detected.get_cell(0, 1), Some("c"));
+
+    let detected = ParsedCsv::parse_detected("x\t\"1,2\"\t\"\"").unwrap();
+    assert_eq!(detected.num_columns_at(0), 3);
+    assert_eq!(detected.get_cell(0, 1), Some("1,2"));
+    assert!(detected.is_quoted(0, 2));
+}
+
+#[test]
+fn escaped_tsv_round_trip() {
+    use super::tsv;
+
+    // Copy dumps escaped TSV, which paste must not detect as quoted data.
+    const CELLS: [&str; 5] = ["\"a\"", "\"\"", "x,\"y\"", "say \"hi\"", "tab\tand \\\""];
+
+    let mut buf = String::new();
+    for (col, data) in CELLS.iter().enumerate() {
+        if col > 0 {
+            tsv::write_tab(&mut buf);
+        }
+
+        tsv::write_content(&mut buf, data);
+    }
+
+    assert!(ParsedCsv::parse_detected(&buf).is_none());
+
+    let parsed = tsv::ParsedTsv::parse(&buf);
+    assert_eq!(parsed.num_columns_at(0), CELLS.len());
+
+    for (col, data) in CELLS.iter().enumerate() {
+        assert_eq!(parsed.get_cell(0, col), Some(*data));
+    }
+}
@@ -0,0 +1,98 @@
+//! Text matching for the find & replace overlay.
+
+use std::ops::Range;
+
+/// Find & replace overlay status. Queries are kept while the overlay is closed.
+#[derive(Default)]
+pub struct FindState {
+    pub is_open: bool,
+    pub query: String,
+    pub replacement: String,
+    pub match_case: bool,
+
+    /// Set if the latest search didn't find anything.
+    pub no_match: bool,
+
+    /// Focus query editor on next frame.
+    pub want_focus: bool,
+}
+
+/// Operation requested from the overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindRequest {
+    Next,
+    Previous,
+    Replace,
+    ReplaceAll,
+}
+
+/// Byte range of the first occurrence of `query` in `text`. Empty query never matches.
+pub fn find_text(text: &str, query: &str, match_case: bool) -> Option<Range<usize>> {
+    if query.is_empty() {
+        return None;
+    }
+
+    if match_case {
+        return text.find(query).map(|begin| begin..begin + query.len());
+    }
+
+    // Compare char by char, as lowercase conversion may change byte length of the text.
+    let query = query.to_lowercase();
+
+    text.char_indices().find_map(|(begin, _)| {
+        let mut remaining = query.chars().peekable();
+
+        for (offset, ch) in text[begin..].char_indices() {
+            for lower in ch.to_lowercase() {
+                if remaining.next() != Some(lower) {
+                    return None;
+                }
+            }
+
+            if remaining.peek().is_none() {
+                return Some(begin..begin + offset + ch.len_utf8());
+            }
+        }
+
+        None
+    })
+}
+
+/// Replaces every occurrence of `query` in `text`. Returns `None` if there's no occurrence.
+pub fn replace_text(text: &str, query: &str, replacement: &str, match_case: bool) -> Option<String> {
+    let mut range = find_text(text, query, match_case)?;
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+
+    loop {
+        out.push_str(&text[cursor..range.start]);
+        out.push_str(replacement);
+        cursor = range.end;
+
+        match find_text(&text[cursor..], query, match_case) {
+            Some(next) => range = next.start + cursor..next.end + cursor,
+            None => break,
+        }
+    }
+
+    out.push_str(&text[cursor..]);
+    Some(out)
+}
+
+#[test]
+fn text_matching() {
+    assert_eq!(find_text("Hello World", "world", false), Some(6..11));
+    assert_eq!(find_text("Hello World", "world", true), None);
+    assert_eq!(find_text("Hello World", "", false), None);
+    assert_eq!(find_text("ÄÖÜ abc", "öü", false), Some(2..6));
+
+    assert_eq!(
+        replace_text("one Two two", "two", "2", false).as_deref(),
+        Some("one 2 2")
+    );
+    assert_eq!(
+        replace_text("one Two two", "two", "2", true).as_deref(),
+        Some("one Two 2")
+    );
+    assert_eq!(replace_text("one", "two", "2", false), None);
+}
@@ -2,6 +2,12 @@ use std::{
     collections::{BTreeMap, BTreeSet, VecDeque},
     hash::{Hash, Hasher},
     mem::{replace, take},
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+    thread::JoinHandle,
 };
 use std::collections::HashSet;
 use ahash::{AHasher, HashMap, HashMapExt};
@@ -11,14 +17,24 @@ use tap::prelude::{Pipe, Tap};
 
 use crate::{
     default,
-    draw::tsv,
+    draw::{
+        csv,
+        find::{self, FindState},
+        tsv,
+    },
+    history::{HistoryEntry, HistoryEntryKind, RowsFingerprint, UndoHistory},
+    io::TextFormat,
+    source::RowQuery,
     viewer::{
-        CellWriteContext, DecodeErrorBehavior, EmptyRowCreateContext, MoveDirection, RowCodec,
-        UiActionContext, UiCursorState,
+        BackgroundRowOps, CellWriteContext, CustomActionOrigin, DecodeErrorBehavior, EmptyRowCreateContext, FillSeries, MoveDirection,
+        RowCodec, SelectionSnapshot, UiActionContext, UiCursorState,
     },
-    DataTable, RowViewer, UiAction,
+    DataTable, RowViewer, UiAction, UserCommand,
 };
 
+#[cfg(feature = "rayon")]
+use crate::viewer::ParallelRowOps;
+
 macro_rules! int_ty {
 (
     $(#[$meta:meta])*
@@ -35,8 +51,10 @@ macro_rules! int_ty {
 int_ty!(
     struct VisLinearIdx(usize);
     struct VisSelection(VisLinearIdx, VisLinearIdx);
+    #[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
     struct RowSlabIndex(usize);
 
+    #[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
     struct RowIdx(usize);
     struct VisRowPos(usize);
     struct VisRowOffset(usize);
@@ -48,6 +66,32 @@ int_ty!(
     struct ColumnIdx(usize);
 );
 
+impl RowIdx {
+    /// Index into the row storage, for use outside of the renderer.
+    pub(crate) fn index(self) -> usize {
+        self.0
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new(index: usize) -> Self {
+        Self(index)
+    }
+}
+
+#[cfg(test)]
+impl ColumnIdx {
+    pub(crate) fn new(index: usize) -> Self {
+        Self(index)
+    }
+}
+
+#[cfg(test)]
+impl IsAscending {
+    pub(crate) fn new(is_ascending: bool) -> Self {
+        Self(is_ascending)
+    }
+}
+
 impl VisSelection {
     pub fn contains(&self, ncol: usize, row: VisRowPos, col: VisColumnPos) -> bool {
         let (top, left) = self.0.row_col(ncol);
@@ -160,6 +204,10 @@ pub(crate) struct UiState<R> {
     #[cfg(feature = "persistency")]
     is_p_loaded: bool,
 
+    /// Persistent data was changed without invalidating cached rows; e.g. layout.
+    #[cfg(feature = "persistency")]
+    is_p_changed: bool,
+
     /*
 
         SECTION: Cache - Rendering
@@ -178,6 +226,23 @@ pub(crate) struct UiState<R> {
     /// Cached row id to visual row position table for quick lookup.
     cc_row_id_to_vis: HashMap<RowIdx, VisRowPos>,
 
+    /// Incremented on every rebuild of `cc_rows`.
+    cc_generation: u64,
+
+    /// Footer contents. See [`Self::validate_footer`].
+    cc_footer: Option<FooterCache>,
+
+    /// Row groups in display order, when grouping is enabled. Rows of collapsed groups are
+    /// excluded from `cc_rows`, just like filtered ones.
+    cc_groups: Vec<RowGroupCache>,
+
+    /// Hash of everything aggregates of groups depend on.
+    cc_group_aggregates_key: Option<u64>,
+
+    /// A group was collapsed or expanded; rows are excluded again on next validation, without
+    /// rebuilding groups.
+    cc_collapse_pending: bool,
+
     /// Spreadsheet is modified during the last validation.
     cc_dirty: bool,
 
@@ -188,6 +253,14 @@ pub(crate) struct UiState<R> {
     /// Number of frames from the last edit. Used to validate sorting.
     cc_num_frame_from_last_edit: usize,
 
+    /// Rows modified while sorted since `cc_rows` was rebuilt, thus may be out of sort order.
+    /// Unsorted rows are left in place until the next rebuild.
+    cc_edited_rows: HashSet<RowIdx>,
+
+    /// Edited rows are moved to their sorted positions on next validation. See
+    /// [`Self::resort_edited_rows`].
+    cc_resort_pending: bool,
+
     /// Cached previous number of columns.
     cc_prev_n_columns: usize,
 
@@ -197,6 +270,33 @@ pub(crate) struct UiState<R> {
     /// Desired selection of next validation
     cc_desired_selection: Option<Vec<(RowIdx, Vec<ColumnIdx>)>>,
 
+    /// Desired interactive cell of next validation.
+    cc_desired_interactive: Option<(RowIdx, ColumnIdx)>,
+
+    /// Modifications of rows not yet delivered to [`crate::source::RowSource`]. `None` unless
+    /// rows are fetched from a source, where sort and filters are applied by the source rather
+    /// than by the cache validation.
+    source_edits: Option<Vec<UserCommand<R>>>,
+
+    /// Rows rendered on the last frame, in storage order. Source-backed tables fetch rows of
+    /// this range.
+    cc_rendered_rows: Range<usize>,
+
+    /// First row appended since the last validation. See [`Self::mark_rows_appended`].
+    cc_appended_from: Option<usize>,
+
+    /// Keys of rows as of the last validation, in storage order. `None` if the viewer does not
+    /// provide [`RowViewer::row_key`].
+    cc_row_keys: Option<Vec<u64>>,
+
+    /// Rebuild of `cc_rows` running in background. Meanwhile, the previous `cc_rows` is kept in
+    /// view, moved along with modification of rows.
+    cc_rebuild: Option<RebuildJob>,
+
+    /// Movement of row indices since the last validation; applied to the previous `cc_rows`
+    /// unless it's rebuilt on the UI thread.
+    cc_row_shifts: Vec<RowShift>,
+
     /*
 
         SECTION: Cache - Input Status
@@ -213,6 +313,50 @@ pub(crate) struct UiState<R> {
 
     /// How many rows are rendered at once recently?
     pub cci_page_row_count: usize,
+
+    /// Format used when dumping copied contents into system clipboard.
+    pub clipboard_format: TextFormat,
+
+    /// Vertical scroll offset shared between panes, when there are frozen columns.
+    pub cci_scroll_offset: f32,
+
+    /// Height of the footer, measured on the last frame.
+    pub cci_footer_height: f32,
+
+    /// Find & replace overlay.
+    pub find: FindState,
+
+    /// Whether any column border was being dragged on the last frame.
+    pub cci_resizing_columns: bool,
+
+    /// Forces reset of column widths on next frame, to apply stored widths.
+    pub cci_reset_column_widths: bool,
+
+    /// Hash of visible columns layout of the last frame.
+    cci_column_layout: u64,
+
+    /// Whether the latest cell context menu was opened from the row header.
+    pub cci_row_header_menu: bool,
+
+    /// Cell which the fill handle is being dragged onto.
+    cci_fill_target: Option<(VisRowPos, VisColumnPos)>,
+
+    /// Maximum number of undo history entries. Updated by the renderer every frame, so that
+    /// commands pushed outside of rendering respect the same limit.
+    pub undo_capacity: usize,
+
+    /// Whether an undo group is open; see [`Command::CcBeginUndoGroup`].
+    undo_group_open: bool,
+
+    /// Whether the front of undo queue was created within the currently open group.
+    undo_group_has_entry: bool,
+
+    /// Incremented on every change of undo queue or cursor.
+    undo_generation: u64,
+
+    /// Generation of undo history which was stored last time. `None` if it was never loaded.
+    #[cfg(feature = "persistency")]
+    undo_p_generation: Option<u64>,
 }
 
 #[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
@@ -226,6 +370,619 @@ struct PersistData {
 
     /// Column sorting state.
     sort: Vec<(ColumnIdx, IsAscending)>,
+
+    /// Filter row contents of each column. Empty filters are not stored.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    column_filters: Vec<(ColumnIdx, String)>,
+
+    /// Number of leading visible columns which don't scroll horizontally.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    frozen_columns: usize,
+
+    /// Number of leading visual rows which don't scroll vertically.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    frozen_rows: usize,
+
+    /// Column which rows are grouped by.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    group_by: Option<ColumnIdx>,
+
+    /// Keys of collapsed groups.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    collapsed_groups: Vec<String>,
+
+    /// Widths of columns resized or auto-fitted by user.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    column_widths: Vec<(ColumnIdx, f32)>,
+}
+
+pub(crate) struct RowGroupCache {
+    pub key: String,
+
+    /// Every row in the group, including collapsed ones.
+    pub rows: Vec<RowIdx>,
+
+    /// Visual position of the first row. For collapsed group, it's where the rows would be.
+    first_vis: usize,
+
+    /// Display position of the header; the rows follow it.
+    display_pos: usize,
+
+    pub is_collapsed: bool,
+
+    /// Aggregates for each visible column. See [`UiState::validate_group_aggregates`].
+    pub aggregates: Vec<Option<String>>,
+}
+
+pub(crate) struct FooterCache {
+    /// Hash of everything the contents depend on.
+    key: u64,
+
+    /// Aggregates for each visible column.
+    pub aggregates: Vec<Option<String>>,
+
+    /// Whether aggregates are computed over selected cells, rather than every visible row.
+    pub is_selection: bool,
+
+    pub num_selected_cells: usize,
+    pub num_selected_rows: usize,
+}
+
+/// A row being rendered; either a group header, or a row of `cc_rows`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum DisplayRow {
+    Group(usize),
+    Row(VisRowPos),
+}
+
+/// Cells to be filled by the fill handle, adjacent to the source selection.
+#[derive(Clone, Copy)]
+struct FillArea {
+    source: VisSelection,
+
+    /// Whether the source is extended along rows, rather than columns.
+    vertical: bool,
+
+    /// Whether the source is extended down or right.
+    forward: bool,
+
+    /// Number of filled rows, or columns.
+    len: usize,
+}
+
+impl FillArea {
+    fn filled(&self, ncol: usize) -> VisSelection {
+        let (top, left) = self.source.0.row_col(ncol);
+        let (bottom, right) = self.source.1.row_col(ncol);
+
+        let ((top, left), (bottom, right)) = match (self.vertical, self.forward) {
+            (true, true) => ((bottom.0 + 1, left.0), (bottom.0 + self.len, right.0)),
+            (true, false) => ((top.0 - self.len, left.0), (top.0 - 1, right.0)),
+            (false, true) => ((top.0, right.0 + 1), (bottom.0, right.0 + self.len)),
+            (false, false) => ((top.0, left.0 - self.len), (bottom.0, left.0 - 1)),
+        };
+
+        VisSelection(
+            VisLinearIdx(top * ncol + left),
+            VisLinearIdx(bottom * ncol + right),
+        )
+    }
+}
+
+/// Column filters in use. Filters of hidden columns are kept, but not applied.
+fn active_column_filters(p: &PersistData) -> Vec<&(ColumnIdx, String)> {
+    p.column_filters
+        .iter()
+        .filter(|(c, _)| p.vis_cols.contains(c))
+        .collect()
+}
+
+/// Row ids within `range` which pass both the viewer's filter and column filters.
+fn filter_rows<R, V: RowViewer<R>>(
+    p: &PersistData,
+    rows: &[R],
+    range: Range<usize>,
+    vwr: &mut V,
+) -> Vec<RowIdx> {
+    let column_filters = active_column_filters(p);
+
+    // Filtering in parallel requires every filter in use to be thread-safe.
+    #[cfg(feature = "rayon")]
+    if let Some(ids) = vwr
+        .parallel_row_ops()
+        .filter(|ops| {
+            ops.filter_row.is_some() && (column_filters.is_empty() || ops.filter_cell.is_some())
+        })
+        .map(|ops| (ops.filter_rows)(&ops, rows, range.clone()))
+    {
+        if column_filters.is_empty() {
+            return ids;
+        }
+
+        // The codec borrows the viewer, thus cells are encoded in between.
+        let encoded = encode_filtered_cells(vwr, &column_filters, rows, ids.iter().copied());
+
+        if let Some(ops) = vwr.parallel_row_ops() {
+            return (ops.filter_cells)(&ops, rows, ids, &column_filters, encoded.as_deref());
+        }
+
+        return filter_row_ids(&column_filters, rows, ids, vwr);
+    }
+
+    filter_row_ids(&column_filters, rows, range.map(RowIdx), vwr)
+}
+
+/// Row ids among `ids` which pass both the viewer's filter and column filters, on the UI thread.
+fn filter_row_ids<R>(
+    column_filters: &[&(ColumnIdx, String)],
+    rows: &[R],
+    ids: impl IntoIterator<Item = RowIdx>,
+    vwr: &mut impl RowViewer<R>,
+) -> Vec<RowIdx> {
+    let ids = ids
+        .into_iter()
+        .filter(|x| vwr.filter_row(&rows[x.0]))
+        .collect_vec();
+
+    if column_filters.is_empty() {
+        return ids;
+    }
+
+    let encoded = encode_filtered_cells(vwr, column_filters, rows, ids.iter().copied());
+
+    ids.into_iter()
+        .enumerate()
+        .filter(|(i, x)| {
+            let row = &rows[x.0];
+            column_filters.iter().enumerate().all(|(j, (c, filter))| {
+                let text = encoded
+                    .as_ref()
+                    .map(|cells| cells[i * column_filters.len() + j].as_str());
+                vwr.filter_cell(row, c.0, filter, text)
+            })
+        })
+        .map(|(_, x)| x)
+        .collect()
+}
+
+/// Cells of filtered columns of the rows, encoded for [`RowViewer::filter_cell`]; the cell of
+/// `i`-th row and `j`-th filter is at `i * column_filters.len() + j`.
+fn encode_filtered_cells<R>(
+    vwr: &mut impl RowViewer<R>,
+    column_filters: &[&(ColumnIdx, String)],
+    rows: &[R],
+    ids: impl IntoIterator<Item = RowIdx>,
+) -> Option<Vec<String>> {
+    encode_cells(
+        vwr,
+        ids.into_iter()
+            .flat_map(|x| column_filters.iter().map(move |(c, _)| (&rows[x.0], *c))),
+    )
+}
+
+/// Encodes cells with a single encoding codec. As the codec borrows the viewer, cells are
+/// encoded ahead of viewer calls which take them, such as [`RowViewer::filter_cell`]. `None`
+/// without a codec.
+fn encode_cells<'a, R: 'a>(
+    vwr: &mut impl RowViewer<R>,
+    cells: impl IntoIterator<Item = (&'a R, ColumnIdx)>,
+) -> Option<Vec<String>> {
+    let mut codec = vwr.try_create_codec(true)?;
+
+    cells
+        .into_iter()
+        .map(|(row, column)| String::new().tap_mut(|buf| codec.encode_column(row, column.0, buf)))
+        .collect_vec()
+        .pipe(Some)
+}
+
+/// Moves items at `from` to `to`, keeping order of the others. Both are sorted, and of the same
+/// length.
+fn move_rows<T>(items: Vec<T>, from: &[RowIdx], to: &[RowIdx]) -> Vec<T> {
+    let num_items = items.len();
+    let mut moved = Vec::with_capacity(from.len());
+    let mut others = Vec::with_capacity(num_items - from.len());
+
+    for (index, item) in items.into_iter().enumerate() {
+        match from.binary_search(&RowIdx(index)) {
+            Ok(_) => moved.push(item),
+            Err(_) => others.push(item),
+        }
+    }
+
+    let (mut moved, mut others) = (moved.into_iter(), others.into_iter());
+    (0..num_items)
+        .map(|index| match to.binary_search(&RowIdx(index)) {
+            Ok(_) => moved.next(),
+            Err(_) => others.next(),
+        })
+        .map(Option::unwrap)
+        .collect()
+}
+
+/// Compares rows by every sort column, in order of priority.
+fn compare_rows<R>(
+    sort: &[(ColumnIdx, IsAscending)],
+    compare_cell: impl Fn(&R, &R, usize) -> std::cmp::Ordering,
+    a: &R,
+    b: &R,
+) -> std::cmp::Ordering {
+    sort.iter().fold(std::cmp::Ordering::Equal, |ord, (col, asc)| {
+        ord.then_with(|| {
+            compare_cell(a, b, col.0).tap_mut(|x| {
+                if !asc.0 {
+                    *x = x.reverse()
+                }
+            })
+        })
+    })
+}
+
+/// Sorts rows with a single stable sort by every sort column, in parallel if the viewer
+/// provides [`ParallelRowOps`].
+fn sort_rows<R, V: RowViewer<R>>(
+    sort: &[(ColumnIdx, IsAscending)],
+    rows: &[R],
+    cc_rows: &mut [RowIdx],
+    vwr: &mut V,
+) {
+    if sort.is_empty() {
+        return;
+    }
+
+    #[cfg(feature = "rayon")]
+    if let Some(ops) = vwr.parallel_row_ops() {
+        (ops.sort_rows)(&ops, sort, rows, cc_rows);
+        return;
+    }
+
+    cc_rows.sort_by(|a, b| {
+        compare_rows(sort, |a, b, col| vwr.compare_cell(a, b, col), &rows[a.0], &rows[b.0])
+    });
+}
+
+#[cfg(feature = "rayon")]
+pub(crate) fn par_filter_rows<R: Sync>(
+    ops: &ParallelRowOps<'_, R>,
+    rows: &[R],
+    range: Range<usize>,
+) -> Vec<RowIdx> {
+    use rayon::prelude::*;
+
+    range
+        .into_par_iter()
+        .filter(|i| ops.filter_row.as_ref().is_some_and(|filter_row| filter_row(&rows[*i])))
+        .map(RowIdx)
+        .collect()
+}
+
+/// Rows among `ids` which pass column filters. `encoded` is given by
+/// [`encode_filtered_cells`].
+#[cfg(feature = "rayon")]
+pub(crate) fn par_filter_cells<R: Sync>(
+    ops: &ParallelRowOps<'_, R>,
+    rows: &[R],
+    ids: Vec<RowIdx>,
+    column_filters: &[&(ColumnIdx, String)],
+    encoded: Option<&[String]>,
+) -> Vec<RowIdx> {
+    use rayon::prelude::*;
+
+    ids.into_par_iter()
+        .enumerate()
+        .filter(|(i, x)| {
+            column_filters.iter().enumerate().all(|(j, (c, filter))| {
+                let text = encoded.map(|cells| cells[i * column_filters.len() + j].as_str());
+                ops.filter_cell
+                    .as_ref()
+                    .is_none_or(|filter_cell| filter_cell(&rows[x.0], c.0, filter, text))
+            })
+        })
+        .map(|(_, x)| x)
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+pub(crate) fn par_sort_rows<R: Sync>(
+    ops: &ParallelRowOps<'_, R>,
+    sort: &[(ColumnIdx, IsAscending)],
+    rows: &[R],
+    cc_rows: &mut [RowIdx],
+) {
+    use rayon::prelude::*;
+
+    cc_rows.par_sort_by(|a, b| compare_rows(sort, &ops.compare, &rows[a.0], &rows[b.0]));
+}
+
+/// Sort and filters which `cc_rows` is rebuilt by. See [`UiState::rebuild_query`].
+#[derive(Clone, PartialEq)]
+pub(crate) struct RebuildQuery {
+    sort: Vec<(ColumnIdx, IsAscending)>,
+    filters: Vec<(ColumnIdx, String)>,
+    viewer_filter_hash: u64,
+}
+
+/// Rebuild of `cc_rows` running on a background thread. See [`RowViewer::background_row_ops`].
+struct RebuildJob {
+    /// Taken once the thread is done.
+    handle: Option<JoinHandle<Vec<RowIdx>>>,
+    cancel: Arc<AtomicBool>,
+    query: RebuildQuery,
+
+    /// Current index of each row whose key was given to the thread; `None` if removed since.
+    remap: Vec<Option<RowIdx>>,
+
+    /// Rows inserted or modified since keys were taken, as current indices. They are filtered and
+    /// sorted into the result once it's done.
+    touched: HashSet<RowIdx>,
+
+    /// Rows were modified in a way which can't be reconciled, e.g. through `DerefMut`; the
+    /// result is still used, but rebuilt once again.
+    rerun: bool,
+}
+
+impl RebuildJob {
+    fn shift(&mut self, shift: &RowShift) {
+        for row in self.remap.iter_mut() {
+            *row = row.and_then(|x| shift.apply(x));
+        }
+
+        self.touched = take(&mut self.touched)
+            .into_iter()
+            .filter_map(|x| shift.apply(x))
+            .collect();
+
+        match shift {
+            RowShift::Insert(pos, count) => {
+                self.touched.extend((pos.0..pos.0 + count).map(RowIdx));
+            }
+            RowShift::Remove(_) => {}
+            // Rows are moved by UI, or modified outside of UI; equal rows of the result are no
+            // longer in storage order, and any row may have been modified.
+            RowShift::Map(_) => self.rerun = true,
+        }
+    }
+}
+
+impl Drop for RebuildJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, AtomicOrdering::Relaxed);
+    }
+}
+
+/// Movement of row indices by a modification of rows. Recorded to keep the previous `cc_rows`
+/// in view while it's rebuilt in background.
+enum RowShift {
+    /// Rows are inserted at the position.
+    Insert(RowIdx, usize),
+    /// Rows are removed, in ascending order.
+    Remove(Vec<RowIdx>),
+    /// New index of every row; `None` if removed.
+    Map(Vec<Option<RowIdx>>),
+}
+
+impl RowShift {
+    fn apply(&self, row: RowIdx) -> Option<RowIdx> {
+        match self {
+            Self::Insert(pos, count) if row >= *pos => Some(RowIdx(row.0 + count)),
+            Self::Insert(..) => Some(row),
+            Self::Remove(removed) => removed.binary_search(&row).err().map(|x| RowIdx(row.0 - x)),
+            Self::Map(map) => map.get(row.0).copied().flatten(),
+        }
+    }
+
+    /// Moves rows of `cc_rows`. Inserted rows are placed before the row they're inserted before,
+    /// or after the one they're inserted after.
+    fn apply_all(&self, cc_rows: &mut Vec<RowIdx>) {
+        cc_rows.retain_mut(|row| {
+            self.apply(*row)
+                .map(|new_row| *row = new_row)
+                .is_some()
+        });
+
+        if let Self::Insert(pos, count) = self {
+            let at = cc_rows
+                .iter()
+                .position(|x| x.0 == pos.0 + count)
+                .or_else(|| cc_rows.iter().position(|x| x.0 + 1 == pos.0).map(|x| x + 1))
+                .unwrap_or(cc_rows.len());
+
+            cc_rows.splice(at..at, (pos.0..pos.0 + count).map(RowIdx));
+        }
+    }
+}
+
+/// Takes keys of rows, then filters and sorts them on a new thread. `encoded` is given by
+/// [`encode_filtered_cells`] for every row.
+fn spawn_rebuild<R, K: Send + 'static>(
+    ops: BackgroundRowOps<R, K>,
+    rows: &[R],
+    query: RebuildQuery,
+    encoded: Option<Vec<String>>,
+    cancel: Arc<AtomicBool>,
+) -> JoinHandle<Vec<RowIdx>> {
+    let keys = rows.iter().map(&ops.key).collect::<Vec<_>>();
+    let BackgroundRowOps { compare, filter_row, filter_cell, .. } = ops;
+
+    std::thread::spawn(move || {
+        let num_filters = query.filters.len();
+        let mut cc_rows = (0..keys.len())
+            .filter(|i| {
+                let key = &keys[*i];
+                filter_row.as_ref().is_some_and(|filter_row| filter_row(key))
+                    && query.filters.iter().enumerate().all(|(j, (c, filter))| {
+                        let text = encoded
+                            .as_ref()
+                            .map(|cells| cells[i * num_filters + j].as_str());
+
+                        filter_cell
+                            .as_ref()
+                            .is_some_and(|filter_cell| filter_cell(key, c.0, filter, text))
+                    })
+            })
+            .map(RowIdx)
+            .collect::<Vec<_>>();
+
+        cc_rows.sort_by(|a, b| {
+            // Finishes the sort early once the result is no longer needed.
+            if cancel.load(AtomicOrdering::Relaxed) {
+                return std::cmp::Ordering::Equal;
+            }
+
+            compare_rows(&query.sort, &compare, &keys[a.0], &keys[b.0])
+        });
+
+        cc_rows
+    })
+}
+
+/// Merges selections of adjacent cells into rectangles; first along rows, then along columns.
+fn merge_selections(ncol: usize, sel: &mut Vec<VisSelection>) {
+    // As (top, left, bottom, right).
+    let mut rects = sel
+        .drain(..)
+        .map(|x| {
+            let (top, left) = x.0.row_col(ncol);
+            let (bottom, right) = x.1.row_col(ncol);
+            (top.0, left.0, bottom.0, right.0)
+        })
+        .collect::<Vec<_>>();
+
+    rects.sort_unstable();
+    rects.dedup();
+    rects = rects.into_iter().coalesce(|a, b| {
+        if a.0 == b.0 && a.2 == b.2 && a.3 + 1 == b.1 {
+            Ok((a.0, a.1, a.2, b.3))
+        } else {
+            Err((a, b))
+        }
+    }).collect();
+
+    rects.sort_unstable_by_key(|&(top, left, _, right)| (left, right, top));
+    sel.extend(
+        rects
+            .into_iter()
+            .coalesce(|a, b| {
+                if a.1 == b.1 && a.3 == b.3 && a.2 + 1 == b.0 {
+                    Ok((a.0, a.1, b.2, a.3))
+                } else {
+                    Err((a, b))
+                }
+            })
+            .map(|(top, left, bottom, right)| {
+                VisSelection(
+                    VisLinearIdx(top * ncol + left),
+                    VisLinearIdx(bottom * ncol + right),
+                )
+            }),
+    );
+}
+
+/// Keys of rows at the same point of undo history, before and after the rows are modified
+/// outside of UI. See [`UiState::rebase_undo_history`].
+#[derive(Clone)]
+struct RowRebase {
+    old: Vec<u64>,
+    new: Vec<u64>,
+
+    /// Key to index of `new`.
+    new_index: HashMap<u64, usize>,
+}
+
+impl RowRebase {
+    fn new(old: &[u64], new: &[u64]) -> Self {
+        Self {
+            old: old.to_vec(),
+            new: new.to_vec(),
+            new_index: default(),
+        }
+        .tap_mut(|x| x.reindex())
+    }
+
+    fn reindex(&mut self) {
+        self.new_index.clear();
+        self.new_index
+            .extend(self.new.iter().enumerate().map(|(i, key)| (*key, i)));
+    }
+
+    fn row(&self, row: RowIdx) -> Option<RowIdx> {
+        self.new_index.get(self.old.get(row.0)?).copied().map(RowIdx)
+    }
+
+    /// Rows are inserted before the row at the position, or at the end.
+    fn insert_pos(&self, pos: RowIdx) -> Option<RowIdx> {
+        if pos.0 == self.old.len() {
+            Some(RowIdx(self.new.len()))
+        } else {
+            self.row(pos)
+        }
+    }
+
+    /// Rewrites commands in order, following the rows modified by each of them.
+    fn apply_all<R, V: RowViewer<R>>(
+        &mut self,
+        cmds: &mut [Command<R>],
+        vwr: &mut V,
+    ) -> Option<()> {
+        cmds.iter_mut().try_for_each(|cmd| self.apply(cmd, vwr))
+    }
+
+    fn apply<R, V: RowViewer<R>>(&mut self, cmd: &mut Command<R>, vwr: &mut V) -> Option<()> {
+        // Keys are only used to match rows between `old` and `new`; therefore keys changed by
+        // cell edits don't need to be tracked.
+        match cmd {
+            Command::SetRowValue(row, _) => {
+                *row = self.row(*row)?;
+            }
+            Command::SetCells { values, .. } => {
+                for (row, ..) in values.iter_mut() {
+                    *row = self.row(*row)?;
+                }
+            }
+            Command::InsertRows(pos, values) => {
+                let new_pos = self.insert_pos(*pos)?;
+                let keys = values
+                    .iter()
+                    .map(|x| vwr.row_key(x))
+                    .collect::<Option<Vec<_>>>()?;
+
+                if keys.iter().any(|key| self.new_index.contains_key(key)) {
+                    // The row was brought back from outside.
+                    return None;
+                }
+
+                self.old.splice(pos.0..pos.0, keys.iter().copied());
+                self.new.splice(new_pos.0..new_pos.0, keys);
+                self.reindex();
+                *pos = new_pos;
+            }
+            Command::RemoveRow(indices) => {
+                let mut new_indices = indices
+                    .iter()
+                    .map(|x| self.row(*x))
+                    .collect::<Option<Vec<_>>>()?;
+                new_indices.sort_unstable();
+
+                for (keys, indices) in [(&mut self.old, &*indices), (&mut self.new, &new_indices)] {
+                    let mut index = 0;
+                    keys.retain(|_| {
+                        let idx_now = index.tap(|_| index += 1);
+                        indices.binary_search(&RowIdx(idx_now)).is_err()
+                    });
+                }
+
+                self.reindex();
+                *indices = new_indices;
+            }
+            Command::MoveRows { .. } => {
+                // Moved rows can't be placed consistently among rows modified from outside.
+                return None;
+            }
+            _ => {}
+        }
+
+        Some(())
+    }
 }
 
 struct Clipboard<R> {
@@ -245,8 +1002,13 @@ impl<R> Clipboard<R> {
     }
 }
 
-struct UndoArg<R> {
-    apply: Command<R>,
+#[derive(Clone)]
+#[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct UndoArg<R> {
+    /// Commands to apply in order, on redo.
+    apply: Vec<Command<R>>,
+
+    /// Commands to apply in order, on undo.
     restore: Vec<Command<R>>,
 }
 
@@ -266,14 +1028,45 @@ impl<R> Default for UiState<R> {
             cci_has_focus: false,
             cc_interactive_cell: VisLinearIdx(0),
             cc_row_id_to_vis: default(),
+            cc_groups: default(),
+            cc_group_aggregates_key: None,
+            cc_collapse_pending: false,
+            cc_generation: 0,
+            cc_footer: None,
             cc_num_frame_from_last_edit: 0,
+            cc_edited_rows: default(),
+            cc_resort_pending: false,
             cc_prev_n_columns: 0,
             cc_desired_selection: None,
+            cc_desired_interactive: None,
+            cc_row_keys: None,
+            cc_appended_from: None,
+            cc_rebuild: None,
+            cc_row_shifts: Vec::new(),
+            source_edits: None,
+            cc_rendered_rows: 0..0,
             cci_want_move_scroll: false,
             cci_page_row_count: 0,
+            clipboard_format: default(),
+            cci_scroll_offset: 0.,
+            cci_footer_height: 0.,
+            find: default(),
+            cci_resizing_columns: false,
+            cci_reset_column_widths: false,
+            cci_column_layout: 0,
+            cci_row_header_menu: false,
+            cci_fill_target: None,
+            undo_capacity: 100,
+            undo_group_open: false,
+            undo_group_has_entry: false,
+            undo_generation: 0,
+            #[cfg(feature = "persistency")]
+            undo_p_generation: None,
             p: default(),
             #[cfg(feature = "persistency")]
             is_p_loaded: false,
+            #[cfg(feature = "persistency")]
+            is_p_changed: false,
         }
     }
 }
@@ -290,7 +1083,12 @@ enum CursorState<R> {
 
 impl<R> UiState<R> {
     pub fn cc_is_dirty(&self) -> bool {
-        self.cc_dirty
+        self.cc_dirty || self.cc_appended_from.is_some() || self.is_rebuilding()
+    }
+
+    /// Whether `cc_rows` is being rebuilt in background.
+    pub fn is_rebuilding(&self) -> bool {
+        self.cc_rebuild.is_some()
     }
 
     pub fn validate_identity<V: RowViewer<R>>(&mut self, vwr: &mut V) {
@@ -320,7 +1118,7 @@ impl<R> UiState<R> {
                 }
 
                 if self.cc_num_frame_from_last_edit == 2 {
-                    self.cc_dirty |= !self.p.sort.is_empty();
+                    self.cc_resort_pending |= !self.p.sort.is_empty();
                 }
             }
 
@@ -336,16 +1134,44 @@ impl<R> UiState<R> {
                 any_sort_invalidated
             };
 
+            // Grouping as well.
+            if let Some(col) = self.p.group_by
+                && !vwr.is_groupable_column(col.0)
+            {
+                self.p.group_by = None;
+                self.cc_dirty = true;
+            }
+
+            // Same for the filter row.
+            self.cc_dirty |= {
+                let mut any_filter_invalidated = false;
+
+                self.p.column_filters.retain(|(c, _)| {
+                    vwr.is_filterable_column(c.0)
+                        .tap(|x| any_filter_invalidated |= !x)
+                });
+
+                any_filter_invalidated
+            };
+
             return;
         }
 
         // Clear the cache
+        let source_edits = take(&mut self.source_edits);
         *self = Default::default();
+        self.source_edits = source_edits;
         self.viewer_type = vwr_type_id;
         self.viewer_filter_hash = vwr_hash;
         self.p.num_columns = num_columns;
 
         self.p.vis_cols.extend((0..num_columns).map(ColumnIdx));
+        self.p.frozen_columns = vwr.frozen_columns();
+        self.p.frozen_rows = vwr.frozen_rows();
+        self.p.group_by = vwr
+            .group_by_column()
+            .filter(|c| *c < num_columns && vwr.is_groupable_column(*c))
+            .map(ColumnIdx);
         self.cc_dirty = true;
     }
 
@@ -368,48 +1194,120 @@ impl<R> UiState<R> {
                 // stored column differs from the current.
                 self.p = p;
 
-                // Only retain valid sorting configuration.
+                // Only retain valid sorting / filtering configuration.
                 self.p.sort.retain(|(col, _)| vwr.is_sortable_column(col.0));
+                self.p.column_filters.retain(|(col, _)| vwr.is_filterable_column(col.0));
+                self.p.group_by = self.p.group_by.filter(|col| vwr.is_groupable_column(col.0));
             }
-        } else if self.cc_dirty {
+        } else if take(&mut self.is_p_changed) | self.cc_dirty {
             // Copy current ui status into persistency storage.
             ctx.memory_mut(|m| m.data.insert_persisted(ui_id, self.p.clone()));
         }
     }
 
     pub fn validate_cc<V: RowViewer<R>>(&mut self, rows: &mut [R], vwr: &mut V) {
-        if !replace(&mut self.cc_dirty, false) {
-            self.handle_desired_selection();
-            return;
+        if replace(&mut self.cc_resort_pending, false)
+            && !self.cc_dirty
+            && !self.resort_edited_rows(rows, vwr)
+        {
+            self.cc_dirty = true;
         }
 
-        // XXX: Boost performance with `rayon`?
-        // - Returning `comparator` which is marked as `Sync`
-        // - For this, `R` also need to be sent to multiple threads safely.
-        // - Maybe we need specialization for `R: Send`?
-
-        // We should validate the entire cache.
-        self.cc_rows.clear();
-        self.cc_rows.extend(
-            rows.iter()
-                .enumerate()
-                .filter_map(|(i, x)| vwr.filter_row(x).then_some(i))
-                .map(RowIdx),
-        );
+        if let Some(from) = self.cc_appended_from.take()
+            && !self.cc_dirty
+        {
+            if self.p.group_by.is_some() {
+                // Groups are rebuilt as a whole.
+                self.cc_dirty = true;
+            } else {
+                self.validate_appended_rows(rows, from.min(rows.len()), vwr);
 
-        for (sort_col, asc) in self.p.sort.iter().rev() {
-            self.cc_rows.sort_by(|a, b| {
-                vwr.compare_cell(&rows[a.0], &rows[b.0], sort_col.0)
-                    .tap_mut(|x| {
-                        if !asc.0 {
-                            *x = x.reverse()
-                        }
-                    })
-            });
+                // Appended rows are already in place.
+                self.cc_row_shifts.clear();
+            }
         }
 
-        // Just refill with neat default height.
-        self.cc_row_heights.resize(self.cc_rows.len(), 20.0);
+        if replace(&mut self.cc_collapse_pending, false) && !self.cc_dirty {
+            self.queue_row_references();
+            self.exclude_collapsed_groups();
+            self.cc_generation += 1;
+
+            self.cc_row_heights.resize(self.cc_rows.len(), 20.0);
+            self.cc_row_id_to_vis.clear();
+            self.cc_row_id_to_vis.extend(
+                self.cc_rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| (*id, VisRowPos(i))),
+            );
+        }
+
+        // Sort or filters were changed since the background rebuild started.
+        if self
+            .cc_rebuild
+            .as_ref()
+            .is_some_and(|job| self.p.group_by.is_some() || job.query != self.rebuild_query())
+        {
+            self.cc_rebuild = None;
+        }
+
+        let is_dirty = replace(&mut self.cc_dirty, false);
+        if is_dirty {
+            // Row keys tell which rows the selection was on, before rows are rebuilt.
+            self.validate_row_keys(rows, vwr);
+            self.cc_dirty = false;
+        }
+
+        let rebuilt = self.poll_rebuild(rows, vwr);
+
+        if !is_dirty && rebuilt.is_none() {
+            self.handle_desired_selection();
+            return;
+        }
+
+        self.cc_generation += 1;
+
+        // We should validate the entire cache.
+        if self.is_source_backed() {
+            // Rows are already filtered and sorted by the source.
+            self.cc_rows = (0..rows.len()).map(RowIdx).collect();
+        } else {
+            let rebuild = (is_dirty && self.cc_rebuild.is_none())
+                || rebuilt.as_ref().is_some_and(|(_, rerun)| *rerun);
+            let background = rebuild
+                .then(|| self.background_row_ops(rows.len(), vwr))
+                .flatten();
+
+            if rebuild && background.is_none() {
+                self.cc_rows = filter_rows(&self.p, rows, 0..rows.len(), vwr);
+                sort_rows(&self.p.sort, rows, &mut self.cc_rows, vwr);
+            } else {
+                // Previous rows stay in view until the background rebuild is done.
+                for shift in self.cc_row_shifts.iter() {
+                    shift.apply_all(&mut self.cc_rows);
+                }
+
+                // Rows may have been modified through `DerefMut`.
+                self.cc_rows.retain(|x| x.0 < rows.len());
+
+                if let Some((cc_rows, _)) = rebuilt {
+                    self.queue_row_references();
+                    self.cc_rows = cc_rows;
+                }
+
+                if let Some(ops) = background {
+                    self.start_rebuild(ops, rows, vwr);
+                }
+            }
+        }
+
+        self.cc_row_shifts.clear();
+        self.cc_edited_rows.clear();
+
+        self.validate_groups(rows, vwr);
+
+        // Just refill with neat default height.
+        self.cc_row_heights.resize(self.cc_rows.len(), 20.0);
 
         self.cc_row_id_to_vis.clear();
         self.cc_row_id_to_vis.extend(
@@ -453,6 +1351,293 @@ impl<R> UiState<R> {
         self.validate_interactive_cell(self.p.vis_cols.len());
     }
 
+    /// Moves rows edited since the last rebuild onto their sorted positions by binary search,
+    /// instead of rebuilding every row. Edited rows are filtered again as well. Returns false if
+    /// the whole cache has to be rebuilt instead, e.g. too many rows are edited.
+    fn resort_edited_rows<V: RowViewer<R>>(&mut self, rows: &[R], vwr: &mut V) -> bool {
+        // Above this, a single rebuild is cheaper than moving rows one by one.
+        const MAX_EDITED_ROWS: usize = 64;
+
+        let edited = take(&mut self.cc_edited_rows);
+
+        // Edited rows are merged into the result of the background rebuild.
+        if self.is_rebuilding() {
+            return true;
+        }
+
+        if edited.len() > MAX_EDITED_ROWS
+            || edited.iter().any(|x| x.0 >= rows.len())
+            || self.p.group_by.is_some()
+            || self.is_source_backed()
+        {
+            return false;
+        }
+
+        self.cc_generation += 1;
+
+        // Keep the selection on the same rows.
+        self.queue_row_references();
+
+        // Both `cc_rows` and `cc_row_id_to_vis` are left intact before `lo`. Unless as many rows
+        // are inserted as removed, the rest from `lo` is shifted.
+        let mut lo = usize::MAX;
+        let mut hi = 0;
+
+        let mut heights = HashMap::new();
+        let removed = edited
+            .iter()
+            .filter_map(|x| self.cc_row_id_to_vis.remove(x))
+            .map(|x| x.0)
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect_vec();
+
+        // In descending order, so that positions of the others remain valid.
+        for &pos in &removed {
+            let row = self.cc_rows.remove(pos);
+            heights.insert(row, self.cc_row_heights.remove(pos));
+            lo = lo.min(pos);
+            hi = hi.max(pos);
+        }
+
+        let column_filters = active_column_filters(&self.p);
+        let inserted = filter_row_ids(&column_filters, rows, edited, vwr)
+            .tap_mut(|x| x.sort_unstable());
+
+        // Ties are broken by storage order, as the stable sort of a full rebuild does.
+        let sort = &self.p.sort;
+        let compare = |a: &RowIdx, b: &RowIdx| {
+            compare_rows(sort, |a, b, col| vwr.compare_cell(a, b, col), &rows[a.0], &rows[b.0])
+                .then(a.cmp(b))
+        };
+
+        for row in &inserted {
+            let pos = self.cc_rows.partition_point(|x| compare(x, row).is_lt());
+            self.cc_rows.insert(pos, *row);
+            self.cc_row_heights
+                .insert(pos, heights.get(row).copied().unwrap_or(20.0));
+            lo = lo.min(pos);
+            hi = hi.max(pos);
+        }
+
+        if let Some(last) = self.cc_rows.len().checked_sub(1)
+            && lo != usize::MAX
+        {
+            // Each inserted row may be pushed down by the ones inserted after it.
+            hi = match removed.len() == inserted.len() {
+                true => hi + inserted.len().saturating_sub(1),
+                false => usize::MAX,
+            }
+            .min(last);
+
+            for vis in lo..=hi {
+                self.cc_row_id_to_vis.insert(self.cc_rows[vis], VisRowPos(vis));
+            }
+        }
+
+        self.handle_desired_selection();
+        self.validate_interactive_cell(self.p.vis_cols.len());
+        true
+    }
+
+    /// Filters and sorts rows appended from `from` into `cc_rows`, without rebuilding the rest.
+    fn validate_appended_rows<V: RowViewer<R>>(&mut self, rows: &[R], from: usize, vwr: &mut V) {
+        self.cc_generation += 1;
+
+        if self.cc_row_keys.is_some() {
+            self.cc_row_keys = self
+                .cc_row_keys
+                .take()
+                .zip(rows[from..].iter().map(|x| vwr.row_key(x)).collect::<Option<Vec<_>>>())
+                .map(|(keys, appended)| keys.tap_mut(|x| x.extend(appended)));
+        }
+
+        let mut appended = if self.is_source_backed() {
+            (from..rows.len()).map(RowIdx).collect()
+        } else {
+            filter_rows(&self.p, rows, from..rows.len(), vwr)
+        };
+
+        if appended.is_empty() {
+            return;
+        }
+
+        if self.p.sort.is_empty() || self.is_source_backed() {
+            // Appended rows come last, just as a full validation would place them.
+            let start = self.cc_rows.len();
+            self.cc_row_id_to_vis.extend(
+                appended
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| (*id, VisRowPos(start + i))),
+            );
+            self.cc_rows.extend(appended);
+        } else {
+            // Existing rows may be pushed down; keep the selection on the same rows.
+            self.queue_row_references();
+
+            let sort = &self.p.sort;
+            let compare = |a: &RowIdx, b: &RowIdx| {
+                compare_rows(sort, |a, b, col| vwr.compare_cell(a, b, col), &rows[a.0], &rows[b.0])
+            };
+
+            // Stable sort and merge place equal rows in storage order, as a full validation
+            // does.
+            appended.sort_by(compare);
+            self.cc_rows = take(&mut self.cc_rows)
+                .into_iter()
+                .merge_by(appended, |a, b| compare(a, b).is_le())
+                .collect();
+
+            self.cc_row_id_to_vis.clear();
+            self.cc_row_id_to_vis.extend(
+                self.cc_rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| (*id, VisRowPos(i))),
+            );
+        }
+
+        self.cc_row_heights.resize(self.cc_rows.len(), 20.0);
+    }
+
+    /// Splits filtered and sorted rows into groups, then excludes rows of collapsed groups.
+    fn validate_groups<V: RowViewer<R>>(&mut self, rows: &[R], vwr: &mut V) {
+        self.cc_groups.clear();
+
+        let Some(group_by) = self.p.group_by else {
+            return;
+        };
+
+        // Groups are ordered by their first row, thus follow the sort order.
+        let mut group_index = HashMap::<String, usize>::new();
+
+        let encoded = encode_cells(vwr, self.cc_rows.iter().map(|x| (&rows[x.0], group_by)));
+
+        for (i, row_id) in self.cc_rows.drain(..).enumerate() {
+            let text = encoded.as_ref().map(|cells| cells[i].as_str());
+            let key = vwr.group_key(&rows[row_id.0], group_by.0, text);
+            let index = *group_index.entry(key).or_insert_with_key(|key| {
+                self.cc_groups.push(RowGroupCache {
+                    is_collapsed: self.p.collapsed_groups.contains(key),
+                    key: key.clone(),
+                    rows: Vec::new(),
+                    first_vis: 0,
+                    display_pos: 0,
+                    aggregates: Vec::new(),
+                });
+
+                self.cc_groups.len() - 1
+            });
+
+            self.cc_groups[index].rows.push(row_id);
+        }
+
+        self.exclude_collapsed_groups();
+    }
+
+    /// Fills `cc_rows` with rows of expanded groups, in group order.
+    fn exclude_collapsed_groups(&mut self) {
+        self.cc_rows.clear();
+
+        for (g, group) in self.cc_groups.iter_mut().enumerate() {
+            group.first_vis = self.cc_rows.len();
+            group.display_pos = g + group.first_vis;
+
+            if !group.is_collapsed {
+                self.cc_rows.extend(group.rows.iter().copied());
+            }
+        }
+    }
+
+    /// Sort and filters in use; the background rebuild is discarded once they're changed.
+    fn rebuild_query(&self) -> RebuildQuery {
+        RebuildQuery {
+            sort: self.p.sort.clone(),
+            filters: active_column_filters(&self.p).into_iter().cloned().collect(),
+            viewer_filter_hash: self.viewer_filter_hash,
+        }
+    }
+
+    /// [`RowViewer::background_row_ops`], if rows can be rebuilt in background.
+    fn background_row_ops<V: RowViewer<R>>(
+        &self,
+        num_rows: usize,
+        vwr: &mut V,
+    ) -> Option<BackgroundRowOps<R, impl Send + 'static>> {
+        // Groups are rebuilt from every row at once.
+        if self.p.group_by.is_some() {
+            return None;
+        }
+
+        vwr.background_row_ops().filter(|ops| {
+            num_rows >= ops.min_rows
+                && ops.filter_row.is_some()
+                && (ops.filter_cell.is_some() || active_column_filters(&self.p).is_empty())
+        })
+    }
+
+    /// Starts rebuilding `cc_rows` from keys of rows, on a new thread.
+    fn start_rebuild<K: Send + 'static, V: RowViewer<R>>(
+        &mut self,
+        ops: BackgroundRowOps<R, K>,
+        rows: &[R],
+        vwr: &mut V,
+    ) {
+        let query = self.rebuild_query();
+        let cancel = Arc::<AtomicBool>::default();
+
+        let column_filters = query.filters.iter().collect_vec();
+        let encoded = (!column_filters.is_empty())
+            .then(|| encode_filtered_cells(vwr, &column_filters, rows, (0..rows.len()).map(RowIdx)))
+            .flatten();
+
+        self.cc_rebuild = Some(RebuildJob {
+            handle: Some(spawn_rebuild(ops, rows, query.clone(), encoded, cancel.clone())),
+            cancel,
+            query,
+            remap: (0..rows.len()).map(|x| Some(RowIdx(x))).collect(),
+            touched: default(),
+            rerun: false,
+        });
+    }
+
+    /// Takes the result of the background rebuild once it's done, moved onto the current row
+    /// indices. Rows inserted or modified meanwhile are filtered and sorted into it. Returns
+    /// whether it has to be rebuilt again, too.
+    fn poll_rebuild<V: RowViewer<R>>(
+        &mut self,
+        rows: &[R],
+        vwr: &mut V,
+    ) -> Option<(Vec<RowIdx>, bool)> {
+        let handle = self.cc_rebuild.as_mut()?.handle.take_if(|x| x.is_finished())?;
+        let job = self.cc_rebuild.take()?;
+        let result = handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+
+        let column_filters = active_column_filters(&self.p);
+        let touched_rows = job.touched.iter().copied().filter(|x| x.0 < rows.len());
+        let mut touched = filter_row_ids(&column_filters, rows, touched_rows, vwr);
+
+        // Ties are broken by storage order, as the stable sort of a full validation does.
+        let sort = &self.p.sort;
+        let compare = |a: &RowIdx, b: &RowIdx| {
+            compare_rows(sort, |a, b, col| vwr.compare_cell(a, b, col), &rows[a.0], &rows[b.0])
+                .then(a.cmp(b))
+        };
+
+        touched.sort_by(compare);
+
+        let cc_rows = result
+            .into_iter()
+            .filter_map(|x| job.remap[x.0])
+            .filter(|x| x.0 < rows.len() && !job.touched.contains(x))
+            .merge_by(touched, |a, b| compare(a, b).is_le())
+            .collect();
+
+        Some((cc_rows, job.rerun))
+    }
+
     pub fn try_update_clipboard_from_string<V: RowViewer<R>>(
         &mut self,
         vwr: &mut V,
@@ -484,7 +1669,10 @@ impl<R> UiState<R> {
 
             # Decoding
 
-            - Every format is regarded as TSV. (only \t, \n matters)
+            - If any field starts with double quote, data is regarded as quoted TSV or CSV;
+              see `csv::ParsedCsv::parse_detected`. In this case, unquoted empty field is
+              regarded as a gap, which is not pasted.
+            - Otherwise, every format is regarded as escaped TSV. (only \t, \n matters)
             - For TSV data with same column count with this table
                 - Parse as full-scale table, then put into clipboard as-is.
             - Column count is less than current table
@@ -500,30 +1688,44 @@ impl<R> UiState<R> {
             return false;
         };
 
-        if let CursorState::Select(selections) = &self.cc_cursor {
+        let selection_offset = if let CursorState::Select(selections) = &self.cc_cursor {
             let Some(first) = selections.first().map(|x| x.0) else {
                 // No selectgion present. Do nothing
                 return false;
             };
 
             let (.., col) = first.row_col(self.p.vis_cols.len());
-            col
+            col.0
         } else {
             // If there's no selection, we'll just ignore the system clipboard input
             return false;
         };
 
-        let selection_offset = if let CursorState::Select(sel) = &self.cc_cursor {
-            sel.first().map_or(0, |idx| {
-                let (_, c) = idx.0.row_col(self.p.vis_cols.len());
-                c.0
-            })
-        } else {
-            0
+        // Each cell is `None` if it's a gap, which should not be pasted.
+        let quoted_view;
+        let tsv_view;
+        let view: Vec<Vec<Option<&str>>> = match csv::ParsedCsv::parse_detected(contents) {
+            Some(parsed) => {
+                quoted_view = parsed;
+                quoted_view
+                    .iter_rows()
+                    .map(|(_, cells)| {
+                        cells
+                            .map(|(_, data, quoted)| (quoted || !data.is_empty()).then_some(data))
+                            .collect()
+                    })
+                    .collect()
+            }
+            None => {
+                tsv_view = tsv::ParsedTsv::parse(contents);
+                tsv_view
+                    .iter_rows()
+                    .map(|(_, cells)| cells.map(|(_, data)| Some(data)).collect())
+                    .collect()
+            }
         };
 
-        let view = tsv::ParsedTsv::parse(contents);
-        let table_width = view.calc_table_width();
+        let table_width = view.iter().map(Vec::len).max().unwrap_or(0);
 
         if table_width > self.p.vis_cols.len() {
             // If the copied data has more columns than current table, we'll just ignore it.
@@ -536,28 +1738,26 @@ impl<R> UiState<R> {
         let mut slab = Vec::new();
         let mut pastes = Vec::new();
 
-        for (row_offset, row_data) in view.iter_rows() {
+        for (row_offset, row_data) in view.into_iter().enumerate() {
             let slab_id = slab.len();
             slab.push(codec.create_empty_decoded_row());
 
             // The restoration point of pastes stack.
             let pastes_restore = pastes.len();
 
-            for (column, data) in row_data {
-                let col_idx = column + selection_offset;
+            for (column, data) in row_data.into_iter().enumerate() {
+                let Some(data) = data else {
+                    continue;
+                };
 
-                if col_idx > self.p.vis_cols.len() {
+                let Some(&col_idx) = self.p.vis_cols.get(column + selection_offset) else {
                     // If the column is out of range, we'll just ignore it.
                     return false;
-                }
+                };
 
-                match codec.decode_column(data, col_idx, &mut slab[slab_id]) {
+                match codec.decode_column(data, col_idx.0, &mut slab[slab_id]) {
                     Ok(_) => {
-                        pastes.push((
-                            VisRowOffset(row_offset),
-                            ColumnIdx(col_idx),
-                            RowSlabIndex(slab_id),
-                        ));
+                        pastes.push((VisRowOffset(row_offset), col_idx, RowSlabIndex(slab_id)));
                     }
                     Err(DecodeErrorBehavior::SkipCell) => {
                         // Skip this cell.
@@ -586,6 +1786,7 @@ impl<R> UiState<R> {
     fn try_dump_clipboard_content<V: RowViewer<R>>(
         clipboard: &Clipboard<R>,
         vwr: &mut V,
+        format: TextFormat,
     ) -> Option<String> {
         // clipboard MUST be sorted before dumping; XXX: add assertion?
         #[allow(unused_mut)]
@@ -610,6 +1811,8 @@ impl<R> UiState<R> {
         let mut buf_tmp = String::new();
         let mut row_cursor = 0;
 
+        let delimiter = format.quoted_delimiter();
+
         for (row, columns, ..) in &clipboard.pastes.iter().chunk_by(|(row, ..)| *row) {
             while row_cursor < row.0 {
                 tsv::write_newline(&mut buf_out);
@@ -620,14 +1823,25 @@ impl<R> UiState<R> {
 
             for (_, column, data_idx) in columns.into_iter() {
                 while column_cursor < column.0 - column_offset {
-                    tsv::write_tab(&mut buf_out);
+                    match delimiter {
+                        Some(delimiter) => csv::write_delimiter(&mut buf_out, delimiter),
+                        None => tsv::write_tab(&mut buf_out),
+                    }
                     column_cursor += 1;
                 }
 
                 let data = &clipboard.slab[data_idx.0];
                 codec.encode_column(data, column.0, &mut buf_tmp);
 
-                tsv::write_content(&mut buf_out, &buf_tmp);
+                match delimiter {
+                    // Empty data is explicitly quoted to be distinguished from gaps. Backslash
+                    // is quoted too, since unquoted data is decoded as escaped TSV.
+                    Some(_) if buf_tmp.is_empty() || buf_tmp.contains('\\') => {
+                        csv::write_quoted(&mut buf_out, &buf_tmp)
+                    }
+                    Some(delimiter) => csv::write_content(&mut buf_out, &buf_tmp, delimiter),
+                    None => tsv::write_content(&mut buf_out, &buf_tmp),
+                }
                 buf_tmp.clear();
             }
         }
@@ -636,6 +1850,15 @@ impl<R> UiState<R> {
     }
 
     fn handle_desired_selection(&mut self) -> bool {
+        let ncol = self.p.vis_cols.len();
+
+        if let Some((row_id, col)) = self.cc_desired_interactive.take()
+            && let Some(vis_row) = self.cc_row_id_to_vis.get(&row_id)
+            && let Some(vis_c) = self.p.vis_cols.iter().position(|x| *x == col)
+        {
+            self.cc_interactive_cell = vis_row.linear_index(ncol, VisColumnPos(vis_c));
+        }
+
         let Some((next_sel, sel)) = self.cc_desired_selection.take().and_then(|x| {
             if let CursorState::Select(vec) = &mut self.cc_cursor {
                 Some((x, vec))
@@ -649,10 +1872,12 @@ impl<R> UiState<R> {
         // If there's any desired selections present for next validation, apply it.
 
         sel.clear();
-        let ncol = self.p.vis_cols.len();
 
         for (row_id, columns) in next_sel {
-            let vis_row = self.cc_row_id_to_vis[&row_id];
+            let Some(&vis_row) = self.cc_row_id_to_vis.get(&row_id) else {
+                // Filtered out, or collapsed.
+                continue;
+            };
 
             if columns.is_empty() {
                 let p_left = vis_row.linear_index(ncol, VisColumnPos(0));
@@ -671,6 +1896,7 @@ impl<R> UiState<R> {
             }
         }
 
+        merge_selections(ncol, sel);
         true
     }
 
@@ -678,107 +1904,684 @@ impl<R> UiState<R> {
         &self.p.vis_cols
     }
 
+    /// Row ids of currently displayed rows, in display order.
+    pub fn vis_row_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cc_rows.iter().map(|x| x.0)
+    }
+
+    /// Column indices of currently displayed columns, in display order.
+    pub fn vis_column_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.p.vis_cols.iter().map(|x| x.0)
+    }
+
     pub fn force_mark_dirty(&mut self) {
         self.cc_dirty = true;
-    }
 
-    pub fn row_editing_cell(&mut self, row_id: RowIdx) -> Option<(bool, VisColumnPos)> {
-        match &mut self.cc_cursor {
-            CursorState::Edit {
-                row,
-                last_focus,
-                next_focus,
-                ..
-            } if *row == row_id => Some((replace(next_focus, false), *last_focus)),
-            _ => None,
+        // Any row may have been modified.
+        if let Some(job) = self.cc_rebuild.as_mut() {
+            job.rerun = true;
         }
     }
 
-    pub fn num_columns(&self) -> usize {
-        self.p.num_columns
+    /// Rows of `range` were appended; only they are filtered and sorted into the view on next
+    /// validation, unless the whole view is invalidated anyway.
+    pub fn mark_rows_appended(&mut self, range: Range<usize>) {
+        let from = range.start;
+        self.cc_appended_from = Some(self.cc_appended_from.map_or(from, |x| x.min(from)));
+        self.record_row_shift(RowShift::Insert(RowIdx(from), range.len()));
     }
 
-    pub fn sort(&self) -> &[(ColumnIdx, IsAscending)] {
-        &self.p.sort
+    /// Records movement of row indices, for the previous `cc_rows` and the background rebuild.
+    fn record_row_shift(&mut self, shift: RowShift) {
+        if let Some(job) = self.cc_rebuild.as_mut() {
+            job.shift(&shift);
+        }
+
+        // Rows appended one by one make a single insertion.
+        if let (Some(RowShift::Insert(pos, count)), RowShift::Insert(new_pos, new_count)) =
+            (self.cc_row_shifts.last_mut(), &shift)
+            && pos.0 + *count == new_pos.0
+        {
+            *count += new_count;
+            return;
+        }
+
+        self.cc_row_shifts.push(shift);
     }
 
-    pub fn unwrap_editing_row_data(&mut self) -> &mut R {
-        match &mut self.cc_cursor {
-            CursorState::Edit { edition, .. } => edition,
-            _ => unreachable!(),
+    /// Records rows moved or modified by the command.
+    fn record_row_changes(&mut self, cmd: &Command<R>, num_rows: usize) {
+        match cmd {
+            Command::SetRowValue(row, _) => {
+                if !self.p.sort.is_empty() {
+                    self.cc_edited_rows.insert(*row);
+                }
+
+                if let Some(job) = self.cc_rebuild.as_mut() {
+                    job.touched.insert(*row);
+                }
+            }
+            Command::SetCells { values, .. } => {
+                if !self.p.sort.is_empty() {
+                    self.cc_edited_rows.extend(values.iter().map(|(row, ..)| *row));
+                }
+
+                if let Some(job) = self.cc_rebuild.as_mut() {
+                    job.touched.extend(values.iter().map(|(row, ..)| *row));
+                }
+            }
+            Command::InsertRows(pos, values) => {
+                self.record_row_shift(RowShift::Insert(*pos, values.len()));
+            }
+            Command::RemoveRow(indices) => {
+                self.record_row_shift(RowShift::Remove(indices.clone()));
+            }
+            Command::MoveRows { from, to } => {
+                let mut others = (0..num_rows)
+                    .map(RowIdx)
+                    .filter(|x| to.binary_search(x).is_err());
+
+                let map = (0..num_rows)
+                    .map(RowIdx)
+                    .map(|row| match from.binary_search(&row) {
+                        Ok(index) => Some(to[index]),
+                        Err(_) => others.next(),
+                    })
+                    .collect();
+
+                self.record_row_shift(RowShift::Map(map));
+            }
+            _ => {}
         }
     }
 
-    pub fn is_editing(&self) -> bool {
-        matches!(self.cc_cursor, CursorState::Edit { .. })
+    /// Drops every state which refers rows by position; i.e. undo history, edition and
+    /// selections. Required when the whole row data is replaced.
+    pub fn invalidate_row_references(&mut self) {
+        self.cc_rebuild = None;
+        self.clear_undo_history();
+        self.cc_cursor = CursorState::Select(default());
+        self.cci_selection = None;
+        self.cc_desired_selection = None;
+        self.cc_desired_interactive = None;
+        self.cc_dirty = true;
     }
 
-    pub fn is_selected(&self, row: VisRowPos, col: VisColumnPos) -> bool {
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            selections
-                .iter()
-                .any(|sel| self.vis_sel_contains(*sel, row, col))
-        } else {
-            false
-        }
+    /// Makes this table render rows fetched from [`crate::source::SourceTable`].
+    pub fn set_source_backed(&mut self) {
+        self.source_edits.get_or_insert_default();
     }
 
-    pub fn is_selected_cci(&self, row: VisRowPos, col: VisColumnPos) -> bool {
-        self.cci_selection.is_some_and(|(pivot, current)| {
-            self.vis_sel_contains(
-                VisSelection::from_points(self.p.vis_cols.len(), pivot, current),
-                row,
-                col,
-            )
-        })
+    pub fn is_source_backed(&self) -> bool {
+        self.source_edits.is_some()
     }
 
-    pub fn is_interactive_row(&self, row: VisRowPos) -> Option<VisColumnPos> {
-        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-        (r == row).then_some(c)
+    /// Modifications of rows made since the last call, in order.
+    pub fn take_source_edits(&mut self) -> Vec<UserCommand<R>> {
+        self.source_edits.as_mut().map(take).unwrap_or_default()
     }
 
-    pub fn interactive_cell(&self) -> (VisRowPos, VisColumnPos) {
-        self.cc_interactive_cell.row_col(self.p.vis_cols.len())
+    /// Whether the source needs to catch up with the table, on the next frame.
+    pub fn has_pending_source_sync(&self) -> bool {
+        self.source_edits
+            .as_ref()
+            .is_some_and(|edits| !edits.is_empty() || self.cc_dirty)
     }
 
-    pub fn cci_sel_update(&mut self, current: VisLinearIdx) {
-        if let Some((_, pivot)) = &mut self.cci_selection {
-            *pivot = current;
-        } else {
-            self.cci_selection = Some((current, current));
-        }
+    /// Rows rendered on the last frame, in storage order. Empty until the first frame.
+    pub fn rendered_rows(&self) -> Range<usize> {
+        self.cc_rendered_rows.clone()
     }
 
-    pub fn cci_sel_update_row(&mut self, row: VisRowPos) {
-    let ncol = self.p.vis_cols.len();
-    self.cci_sel_update(row.linear_index(ncol, VisColumnPos(0)));
-    self.cci_sel_update(row.linear_index(ncol, VisColumnPos(ncol - 1)));
+    /// Returns whether the range is changed.
+    pub(crate) fn set_rendered_rows(&mut self, rows: Range<usize>) -> bool {
+        replace(&mut self.cc_rendered_rows, rows.clone()) != rows
     }
 
-    pub fn has_cci_selection(&self) -> bool {
-        self.cci_selection.is_some()
+    /// Sort and filters of visible columns, configured by user.
+    pub fn source_query(&self) -> RowQuery {
+        RowQuery {
+            sort: self.p.sort.iter().map(|(c, asc)| (c.0, asc.0)).collect(),
+            filters: self
+                .p
+                .column_filters
+                .iter()
+                .filter(|(c, _)| self.p.vis_cols.contains(c))
+                .map(|(c, filter)| (c.0, filter.clone()))
+                .collect(),
+        }
     }
 
-    pub fn vis_sel_contains(&self, sel: VisSelection, row: VisRowPos, col: VisColumnPos) -> bool {
-        sel.contains(self.p.vis_cols.len(), row, col)
+    /// Whether rows are tracked by [`RowViewer::row_key`].
+    pub fn has_row_keys(&self) -> bool {
+        self.cc_row_keys.is_some()
     }
 
-    fn get_highlight_changes<'a>(
-        &'a self,
-        table: &'a DataTable<R>,
-        sel: &[VisSelection],
-    ) -> (Vec<&'a R>, Vec<&'a R>) {
-        let mut ohs: BTreeSet<&VisSelection> = BTreeSet::default();
-        let nhs: BTreeSet<&VisSelection> = sel.iter().collect();
+    /// Updates row keys of the rows modified by a command, which already takes care of every
+    /// reference to rows.
+    fn sync_row_keys<V: RowViewer<R>>(&mut self, cmd: &Command<R>, rows: &[R], vwr: &mut V) {
+        let Some(keys) = self.cc_row_keys.as_mut() else {
+            return;
+        };
 
-        if let CursorState::Select(s) = &self.cc_cursor {
-            ohs = s.iter().collect();
+        let mut update = |row: RowIdx| vwr.row_key(&rows[row.0]).map(|key| keys[row.0] = key);
+        let is_valid = match cmd {
+            Command::SetRowValue(row, _) => update(*row).is_some(),
+            Command::SetCells { values, .. } => {
+                values.iter().all(|(row, ..)| update(*row).is_some())
+            }
+            Command::InsertRows(pos, values) => rows[pos.0..pos.0 + values.len()]
+                .iter()
+                .map(|x| vwr.row_key(x))
+                .collect::<Option<Vec<_>>>()
+                .map(|inserted| {
+                    keys.splice(pos.0..pos.0, inserted);
+                })
+                .is_some(),
+            Command::RemoveRow(indices) => {
+                let mut index = 0;
+                keys.retain(|_| {
+                    index += 1;
+                    indices.binary_search(&RowIdx(index - 1)).is_err()
+                });
+                true
+            }
+            Command::MoveRows { from, to } => {
+                *keys = move_rows(take(keys), from, to);
+                true
+            }
+            _ => true,
+        };
+
+        if !is_valid {
+            self.cc_row_keys = None;
         }
+    }
 
-        // IMPORTANT the new highlight may include a selection that includes the old highlight selection
-        //           this happens when making a second multi-select using shift
-        //           e.g.   old: 1..=5, 10..=10, new: 1..=5, 10..=15
+    /// Detects modification of rows made outside of UI by comparing row keys, then moves every
+    /// reference to rows, i.e. selection, edition and undo history, onto the new row indices.
+    /// Selection is queued as row ids on every call, so that it follows rows across re-sorts
+    /// of the next cache validation.
+    pub fn validate_row_keys<V: RowViewer<R>>(&mut self, rows: &[R], vwr: &mut V) {
+        let Some(new_keys) = rows.iter().map(|x| vwr.row_key(x)).collect::<Option<Vec<_>>>()
+        else {
+            self.cc_row_keys = None;
+            return;
+        };
+
+        let Some(old_keys) = self.cc_row_keys.replace(new_keys.clone()) else {
+            // Nothing refers rows by key yet.
+            return;
+        };
+
+        let key_to_row = (old_keys != new_keys).then(|| {
+            new_keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (*key, RowIdx(i)))
+                .collect::<HashMap<_, _>>()
+        });
+
+        let map_row = |row: RowIdx| match &key_to_row {
+            Some(table) => old_keys.get(row.0).and_then(|key| table.get(key)).copied(),
+            None => Some(row),
+        };
+
+        self.queue_row_references();
+        self.cc_desired_interactive = self
+            .cc_desired_interactive
+            .and_then(|(row, col)| Some((map_row(row)?, col)));
+
+        if let Some(desired) = self.cc_desired_selection.as_mut() {
+            *desired = take(desired)
+                .into_iter()
+                .filter_map(|(row, columns)| Some((map_row(row)?, columns)))
+                .collect();
+        }
+
+        if let CursorState::Edit { row, .. } = &mut self.cc_cursor {
+            match map_row(*row) {
+                Some(new_row) => *row = new_row,
+                None => {
+                    // The row being edited is gone.
+                    self.cc_cursor = CursorState::Select(default());
+                }
+            }
+        }
+
+        let Some(key_to_row) = key_to_row else {
+            return;
+        };
+
+        self.record_row_shift(RowShift::Map(
+            old_keys.iter().map(|key| key_to_row.get(key).copied()).collect(),
+        ));
+
+        // Positions of dragged selection are no longer valid.
+        self.cci_selection = None;
+        self.cci_fill_target = None;
+        self.cc_dirty = true;
+
+        // Appending rows does not move any existing row.
+        if !new_keys.starts_with(&old_keys) && !self.rebase_undo_history(&old_keys, &new_keys, vwr)
+        {
+            self.clear_undo_history();
+        }
+    }
+
+    /// Queues the interactive cell and the selection by row ids, so that they stay on the same
+    /// rows after `cc_rows` is rebuilt. Already queued ones are kept.
+    fn queue_row_references(&mut self) {
+        let ncol = self.p.vis_cols.len();
+        if ncol == 0 {
+            return;
+        }
+
+        if self.cc_desired_interactive.is_none() {
+            let (r, c) = self.cc_interactive_cell.row_col(ncol);
+            self.cc_desired_interactive = self
+                .cc_rows
+                .get(r.0)
+                .zip(self.p.vis_cols.get(c.0))
+                .map(|(row, col)| (*row, *col));
+        }
+
+        if self.cc_desired_selection.is_none()
+            && let CursorState::Select(sel) = &self.cc_cursor
+        {
+            let mut rows = Vec::new();
+
+            for sel in sel.iter() {
+                let (top, left) = sel.0.row_col(ncol);
+                let (bottom, right) = sel.1.row_col(ncol);
+                let is_full_row = left.0 == 0 && right.0 + 1 == ncol;
+
+                for row in self.cc_rows.iter().take(bottom.0 + 1).skip(top.0) {
+                    let columns = if is_full_row {
+                        Vec::new()
+                    } else {
+                        self.p.vis_cols.iter().take(right.0 + 1).skip(left.0).copied().collect()
+                    };
+
+                    rows.push((*row, columns));
+                }
+            }
+
+            self.cc_desired_selection = Some(rows);
+        }
+    }
+
+    /// Rewrites row indices of undo history, from the rows of `old_keys` to the rows of
+    /// `new_keys`. Returns false if any entry can't be rewritten, e.g. it refers to a removed
+    /// row.
+    fn rebase_undo_history<V: RowViewer<R>>(
+        &mut self,
+        old_keys: &[u64],
+        new_keys: &[u64],
+        vwr: &mut V,
+    ) -> bool {
+        let cursor = self.undo_cursor;
+        let mut rebase_all = || {
+            // Undo entries are rewritten backward from the current rows, and redo entries are
+            // rewritten forward.
+            let mut rebase = RowRebase::new(old_keys, new_keys);
+
+            for entry in self.undo_queue.iter_mut().skip(cursor) {
+                rebase.apply_all(&mut entry.restore, vwr)?;
+                rebase.clone().apply_all(&mut entry.apply, vwr)?;
+            }
+
+            let mut rebase = RowRebase::new(old_keys, new_keys);
+
+            for entry in self.undo_queue.iter_mut().take(cursor).rev() {
+                rebase.apply_all(&mut entry.apply, vwr)?;
+                rebase.clone().apply_all(&mut entry.restore, vwr)?;
+            }
+
+            Some(())
+        };
+
+        let rebased = rebase_all().is_some();
+        self.undo_generation += 1;
+        rebased
+    }
+
+    pub fn row_editing_cell(&mut self, row_id: RowIdx) -> Option<(bool, VisColumnPos)> {
+        match &mut self.cc_cursor {
+            CursorState::Edit {
+                row,
+                last_focus,
+                next_focus,
+                ..
+            } if *row == row_id => Some((replace(next_focus, false), *last_focus)),
+            _ => None,
+        }
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.p.num_columns
+    }
+
+    pub fn sort(&self) -> &[(ColumnIdx, IsAscending)] {
+        &self.p.sort
+    }
+
+    pub fn column_filter(&self, column: ColumnIdx) -> &str {
+        self.p
+            .column_filters
+            .iter()
+            .find_map(|(c, filter)| (*c == column).then_some(filter.as_str()))
+            .unwrap_or_default()
+    }
+
+    pub fn has_column_filters(&self) -> bool {
+        !self.p.column_filters.is_empty()
+    }
+
+    /// Filter edition is not recorded in undo history; as it's applied on every keystroke.
+    pub fn set_column_filter(&mut self, column: ColumnIdx, filter: String) {
+        self.p.column_filters.retain(|(c, _)| *c != column);
+
+        if !filter.is_empty() {
+            self.p.column_filters.push((column, filter));
+        }
+
+        self.cc_dirty = true;
+    }
+
+    pub fn clear_column_filters(&mut self) {
+        if !self.p.column_filters.is_empty() {
+            self.p.column_filters.clear();
+            self.cc_dirty = true;
+        }
+    }
+
+    /// Number of frozen columns, which always leaves at least one column scrollable.
+    pub fn frozen_columns(&self) -> usize {
+        self.p
+            .frozen_columns
+            .min(self.p.vis_cols.len().saturating_sub(1))
+    }
+
+    pub fn frozen_rows(&self) -> usize {
+        self.p.frozen_rows.min(self.num_display_rows())
+    }
+
+    /// Freezing is a view configuration like filters; not recorded in undo history.
+    pub fn set_frozen_columns(&mut self, count: usize) {
+        self.p.frozen_columns = count;
+        self.mark_p_changed();
+    }
+
+    /// Persists a change of layout, which doesn't require rebuilding rows.
+    fn mark_p_changed(&mut self) {
+        #[cfg(feature = "persistency")]
+        {
+            self.is_p_changed = true;
+        }
+    }
+
+    pub fn column_width(&self, col: ColumnIdx) -> Option<f32> {
+        self.p
+            .column_widths
+            .iter()
+            .find_map(|(c, width)| (*c == col).then_some(*width))
+    }
+
+    pub fn set_column_widths(&mut self, widths: impl IntoIterator<Item = (ColumnIdx, f32)>) {
+        for (col, width) in widths {
+            match self.p.column_widths.iter_mut().find(|(c, _)| *c == col) {
+                Some((_, stored)) => *stored = width,
+                None => self.p.column_widths.push((col, width)),
+            }
+        }
+
+        self.mark_p_changed();
+    }
+
+    /// Returns if column widths should be reset to apply stored ones; when requested, or when
+    /// visible columns have changed since the last call. Table widgets remember widths by
+    /// position, which don't follow reordered columns.
+    pub fn validate_column_layout(&mut self) -> bool {
+        let key = AHasher::default().pipe(|mut hsh| {
+            (&self.p.vis_cols, self.p.frozen_columns).hash(&mut hsh);
+            hsh.finish()
+        });
+
+        let is_changed = replace(&mut self.cci_column_layout, key) != key;
+        replace(&mut self.cci_reset_column_widths, false) || is_changed
+    }
+
+    pub fn set_frozen_rows(&mut self, count: usize) {
+        self.p.frozen_rows = count;
+        self.mark_p_changed();
+    }
+
+    pub fn group_by(&self) -> Option<ColumnIdx> {
+        self.p.group_by
+    }
+
+    /// Grouping is a view configuration like filters; not recorded in undo history.
+    pub fn set_group_by(&mut self, column: Option<ColumnIdx>) {
+        if self.p.group_by != column {
+            self.p.group_by = column;
+            self.p.collapsed_groups.clear();
+            self.cc_dirty = true;
+        }
+    }
+
+    pub fn group(&self, index: usize) -> &RowGroupCache {
+        &self.cc_groups[index]
+    }
+
+    pub fn toggle_group_collapsed(&mut self, index: usize) {
+        let group = &mut self.cc_groups[index];
+        group.is_collapsed = !group.is_collapsed;
+
+        if let Some(pos) = self.p.collapsed_groups.iter().position(|x| *x == group.key) {
+            self.p.collapsed_groups.swap_remove(pos);
+        } else {
+            self.p.collapsed_groups.push(group.key.clone());
+        }
+
+        // Rows are still being rendered; they're excluded on next validation.
+        self.cc_collapse_pending = true;
+        self.mark_p_changed();
+    }
+
+    /// Recomputes [`RowViewer::column_aggregate`] of every group, only if rows, columns or data
+    /// changed since the last call.
+    pub fn validate_group_aggregates(&mut self, table: &DataTable<R>, vwr: &mut impl RowViewer<R>) {
+        let key = AHasher::default().pipe(|mut hsh| {
+            (self.cc_generation, self.undo_generation, &self.p.vis_cols).hash(&mut hsh);
+            hsh.finish()
+        });
+
+        if self.cc_group_aggregates_key.replace(key) == Some(key) {
+            return;
+        }
+
+        for group in &mut self.cc_groups {
+            let rows = group.rows.iter().map(|x| &table.rows[x.0]).collect_vec();
+            group.aggregates = self
+                .p
+                .vis_cols
+                .iter()
+                .map(|column| vwr.column_aggregate(&rows, column.0))
+                .collect();
+        }
+    }
+
+    /// Number of rendered rows, including group headers.
+    pub fn num_display_rows(&self) -> usize {
+        self.cc_rows.len() + self.cc_groups.len()
+    }
+
+    pub fn display_row(&self, index: usize) -> DisplayRow {
+        if self.cc_groups.is_empty() {
+            return DisplayRow::Row(VisRowPos(index));
+        }
+
+        let g = self
+            .cc_groups
+            .partition_point(|group| group.display_pos <= index)
+            - 1;
+
+        if index == self.cc_groups[g].display_pos {
+            DisplayRow::Group(g)
+        } else {
+            DisplayRow::Row(VisRowPos(index - g - 1))
+        }
+    }
+
+    /// Inverse of [`Self::display_row`].
+    pub fn display_index(&self, vis_row: VisRowPos) -> usize {
+        if self.cc_groups.is_empty() {
+            return vis_row.0;
+        }
+
+        let g = self
+            .cc_groups
+            .partition_point(|group| group.first_vis <= vis_row.0)
+            .saturating_sub(1);
+
+        g + 1 + vis_row.0
+    }
+
+    pub fn unwrap_editing_row_data(&mut self) -> &mut R {
+        match &mut self.cc_cursor {
+            CursorState::Edit { edition, .. } => edition,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self.cc_cursor, CursorState::Edit { .. })
+    }
+
+    /// First visible column of the edited row which fails [`RowViewer::validate_cell`], among the
+    /// focused one and the ones changed from the stored row. Columns are told changed by their
+    /// encoded text, or by [`RowViewer::compare_cell`] without a codec.
+    fn rejected_edit_column(
+        &self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+    ) -> Option<VisColumnPos> {
+        let CursorState::Edit {
+            row,
+            edition,
+            last_focus,
+            ..
+        } = &self.cc_cursor
+        else {
+            return None;
+        };
+
+        let stored = &table.rows[row.0];
+        let vis_cols = &self.p.vis_cols;
+        let changed = match encode_cells(
+            vwr,
+            vis_cols.iter().flat_map(|col| [(stored, *col), (edition, *col)]),
+        ) {
+            Some(texts) => texts.chunks(2).map(|x| x[0] != x[1]).collect_vec(),
+            None => vis_cols
+                .iter()
+                .map(|col| vwr.compare_cell(stored, edition, col.0).is_ne())
+                .collect(),
+        };
+
+        (0..vis_cols.len())
+            .map(VisColumnPos)
+            .filter(|pos| pos == last_focus || changed[pos.0])
+            .find(|pos| vwr.validate_cell(edition, vis_cols[pos.0].0).is_error())
+    }
+
+    /// Returns if the command can be applied while the editor is kept open, as its commit was
+    /// refused. Such command changes neither rows nor the cursor, and keeps the edited column
+    /// visible.
+    fn is_applicable_while_editing(&self, command: &Command<R>) -> bool {
+        match command {
+            Command::SetColumnSort(_)
+            | Command::CcHideColumn(_)
+            | Command::CcShowColumn { .. }
+            | Command::CcReorderColumn { .. }
+            | Command::CcUpdateSystemClipboard(_) => true,
+            Command::SetVisibleColumns(cols) => match &self.cc_cursor {
+                CursorState::Edit { last_focus, .. } => {
+                    cols.contains(&self.p.vis_cols[last_focus.0])
+                }
+                CursorState::Select(_) => true,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn is_selected(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            selections
+                .iter()
+                .any(|sel| self.vis_sel_contains(*sel, row, col))
+        } else {
+            false
+        }
+    }
+
+    pub fn is_selected_cci(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        self.cci_selection.is_some_and(|(pivot, current)| {
+            self.vis_sel_contains(
+                VisSelection::from_points(self.p.vis_cols.len(), pivot, current),
+                row,
+                col,
+            )
+        })
+    }
+
+    pub fn is_interactive_row(&self, row: VisRowPos) -> Option<VisColumnPos> {
+        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+        (r == row).then_some(c)
+    }
+
+    pub fn interactive_cell(&self) -> (VisRowPos, VisColumnPos) {
+        self.cc_interactive_cell.row_col(self.p.vis_cols.len())
+    }
+
+    pub fn cci_sel_update(&mut self, current: VisLinearIdx) {
+        if let Some((_, pivot)) = &mut self.cci_selection {
+            *pivot = current;
+        } else {
+            self.cci_selection = Some((current, current));
+        }
+    }
+
+    pub fn cci_sel_update_row(&mut self, row: VisRowPos) {
+    let ncol = self.p.vis_cols.len();
+    self.cci_sel_update(row.linear_index(ncol, VisColumnPos(0)));
+    self.cci_sel_update(row.linear_index(ncol, VisColumnPos(ncol - 1)));
+    }
+
+    pub fn has_cci_selection(&self) -> bool {
+        self.cci_selection.is_some()
+    }
+
+    pub fn vis_sel_contains(&self, sel: VisSelection, row: VisRowPos, col: VisColumnPos) -> bool {
+        sel.contains(self.p.vis_cols.len(), row, col)
+    }
+
+    fn get_highlight_changes<'a>(
+        &'a self,
+        table: &'a DataTable<R>,
+        sel: &[VisSelection],
+    ) -> (Vec<&'a R>, Vec<&'a R>) {
+        let mut ohs: BTreeSet<&VisSelection> = BTreeSet::default();
+        let nhs: BTreeSet<&VisSelection> = sel.iter().collect();
+
+        if let CursorState::Select(s) = &self.cc_cursor {
+            ohs = s.iter().collect();
+        }
+
+        // IMPORTANT the new highlight may include a selection that includes the old highlight selection
+        //           this happens when making a second multi-select using shift
+        //           e.g.   old: 1..=5, 10..=10, new: 1..=5, 10..=15
 
         /// Flatten a set of ranges into a set of linear indices, e.g. [(1,3), (6,8)] -> [1,2,3,6,7,8]
         fn flatten_ranges(ranges: &[(usize, usize)]) -> HashSet<usize> {
@@ -790,7 +2593,7 @@ impl<R> UiState<R> {
         /// Only keep elements in old_rows that are NOT in new_rows
         fn deselected_rows(old_rows: &HashSet<usize>, new_rows: &HashSet<usize>) -> Vec<usize> {
             let missing: Vec<usize> = old_rows
-                .difference(&new_rows)
+                .difference(new_rows)
                 .copied()
                 .collect();
 
@@ -825,7 +2628,7 @@ impl<R> UiState<R> {
         (highlighted, unhighlighted)
     }
 
-    fn make_row_range<'a>(&'a self, nhs: &BTreeSet<&VisSelection>) -> Vec<(usize, usize)> {
+    fn make_row_range(&self, nhs: &BTreeSet<&VisSelection>) -> Vec<(usize, usize)> {
         nhs.iter().map(|sel| {
             let (start_ic_r, _ic_c) = sel.0.row_col(self.p.vis_cols.len());
             let (end_ic_r, _ic_c) = sel.1.row_col(self.p.vis_cols.len());
@@ -840,13 +2643,37 @@ impl<R> UiState<R> {
         command: Command<R>,
         capacity: usize,
     ) {
-        if self.is_editing() && !matches!(command, Command::CcCancelEdit | Command::CcCommitEdit) {
+        if self.is_editing()
+            && !matches!(
+                command,
+                Command::CcCancelEdit
+                    | Command::CcCommitEdit
+                    | Command::CcBeginUndoGroup
+                    | Command::CcEndUndoGroup
+            )
+        {
             // If any non-editing command is pushed while editing, commit it first
             self.push_new_command(table, vwr, Command::CcCommitEdit, capacity);
+
+            if self.is_editing() && !self.is_applicable_while_editing(&command) {
+                // Commit was refused due to invalid value; keep the editor open, and drop the
+                // command. See `RowViewer::reject_invalid_edits`.
+                return;
+            }
         }
 
         // Generate redo argument from command
         let restore = match command {
+            Command::CcBeginUndoGroup => {
+                self.undo_group_open = true;
+                self.undo_group_has_entry = false;
+                return;
+            }
+            Command::CcEndUndoGroup => {
+                self.undo_group_open = false;
+                self.undo_group_has_entry = false;
+                return;
+            }
             Command::CcHideColumn(column_idx) => {
                 if self.p.vis_cols.len() == 1 {
                     return;
@@ -911,6 +2738,28 @@ impl<R> UiState<R> {
                 return;
             }
             ref cmd @ (Command::CcCancelEdit | Command::CcCommitEdit) => {
+                if matches!(cmd, Command::CcCommitEdit)
+                    && vwr.reject_invalid_edits()
+                    && let Some(rejected) = self.rejected_edit_column(table, vwr)
+                    && let CursorState::Edit {
+                        row,
+                        last_focus,
+                        next_focus,
+                        ..
+                    } = &mut self.cc_cursor
+                {
+                    // Give focus back to the editor, as committing usually releases it.
+                    *last_focus = rejected;
+                    *next_focus = true;
+
+                    if let Some(vis_row) = self.cc_row_id_to_vis.get(row) {
+                        self.cc_interactive_cell =
+                            vis_row.linear_index(self.p.vis_cols.len(), rejected);
+                    }
+
+                    return;
+                }
+
                 // This edition state become selection. Restorat
                 let Some((row_id, edition, _)) = self.try_take_edition() else {
                     return;
@@ -1045,12 +2894,33 @@ impl<R> UiState<R> {
                     })
                     .collect()
             }
+            Command::MoveRows { ref from, ref to } => {
+                if from == to {
+                    return;
+                }
+
+                vec![Command::MoveRows {
+                    from: to.clone(),
+                    to: from.clone(),
+                }]
+            }
             Command::CcUpdateSystemClipboard(..) => {
                 // This command MUST've be consumed before calling this.
                 unreachable!()
             }
         };
 
+        if self.undo_group_has_entry && self.undo_cursor == 0 {
+            // Merge into the entry of current group. Later changes must be reverted first.
+            self.cmd_apply(table, vwr, &command);
+
+            let front = self.undo_queue.front_mut().unwrap();
+            front.apply.push(command);
+            front.restore.splice(0..0, restore);
+            self.undo_generation += 1;
+            return;
+        }
+
         // Discard all redos after this point.
         self.undo_queue.drain(0..self.undo_cursor);
 
@@ -1066,9 +2936,12 @@ impl<R> UiState<R> {
 
         // Push the command to the queue.
         self.undo_queue.push_front(UndoArg {
-            apply: command,
+            apply: vec![command],
             restore,
         });
+
+        self.undo_group_has_entry = self.undo_group_open;
+        self.undo_generation += 1;
     }
 
     fn cmd_apply<V: RowViewer<R>>(
@@ -1079,6 +2952,13 @@ impl<R> UiState<R> {
     ) {
         match cmd {
             Command::SetVisibleColumns(cols) => {
+                // The editor follows its column.
+                if let CursorState::Edit { last_focus, .. } = &mut self.cc_cursor
+                    && let Some(pos) = cols.iter().position(|x| *x == self.p.vis_cols[last_focus.0])
+                {
+                    *last_focus = VisColumnPos(pos);
+                }
+
                 self.validate_interactive_cell(cols.len());
                 self.p.vis_cols.clear();
                 self.p.vis_cols.extend(cols.iter().cloned());
@@ -1104,13 +2984,13 @@ impl<R> UiState<R> {
                 let mut modified_rows: HashMap<RowIdx, R> = HashMap::new();
                 
                 for (row, col, value_id) in values.iter() {
-                    let _ = modified_rows.entry(row.clone()).or_insert_with(|| vwr.clone_row(&table.rows[row.0]));
+                    let _ = modified_rows.entry(*row).or_insert_with(|| vwr.clone_row(&table.rows[row.0]));
                     
                     vwr.set_cell_value(&slab[value_id.0], &mut table.rows[row.0], col.0);
                 }
 
                 for (row, old_row) in modified_rows.iter() {
-                    vwr.on_row_updated(row.0, &mut table.rows[row.0], old_row);
+                    vwr.on_row_updated(row.0, &table.rows[row.0], old_row);
                 }
             }
             Command::InsertRows(pos, values) => {
@@ -1123,7 +3003,7 @@ impl<R> UiState<R> {
                 let range = pos.0..pos.0 + values.len();
                 
                 for row_index in range.clone() {
-                    vwr.on_row_inserted(row_index, &mut table.rows[row_index]);
+                    vwr.on_row_inserted(row_index, &table.rows[row_index]);
                 }
                 self.queue_select_rows(range.map(RowIdx));
             }
@@ -1133,7 +3013,7 @@ impl<R> UiState<R> {
                 table.dirty_flag = true;
 
                 for row_index in values.iter() {
-                    vwr.on_row_removed(row_index.0, &mut table.rows[row_index.0]);
+                    vwr.on_row_removed(row_index.0, &table.rows[row_index.0]);
                 }
                 
                 let mut index = 0;
@@ -1144,6 +3024,21 @@ impl<R> UiState<R> {
 
                 self.queue_select_rows([]);
             }
+            Command::MoveRows { from, to } => {
+                debug_assert!(from.len() == to.len());
+                debug_assert!(from.windows(2).all(|x| x[0] < x[1]));
+                debug_assert!(to.windows(2).all(|x| x[0] < x[1]));
+                self.cc_dirty = true; // It invalidates all current `RowId` occurrences.
+                table.dirty_flag = true;
+
+                table.rows = move_rows(take(&mut table.rows), from, to);
+
+                for (src, dst) in from.iter().zip(to) {
+                    vwr.on_row_moved(src.0, dst.0, &table.rows[dst.0]);
+                }
+
+                self.queue_select_rows(to.iter().copied());
+            }
             Command::CcHideColumn(..)
             | Command::CcShowColumn { .. }
             | Command::CcReorderColumn { .. }
@@ -1152,8 +3047,65 @@ impl<R> UiState<R> {
             | Command::CcCancelEdit
             | Command::CcSetSelection(..)
             | Command::CcSetCells { .. }
+            | Command::CcBeginUndoGroup
+            | Command::CcEndUndoGroup
             | Command::CcUpdateSystemClipboard(..) => unreachable!(),
         }
+
+        if matches!(
+            cmd,
+            Command::SetRowValue(..)
+                | Command::SetCells { .. }
+                | Command::InsertRows(..)
+                | Command::RemoveRow(..)
+                | Command::MoveRows { .. }
+        ) {
+            self.sync_row_keys(cmd, &table.rows, vwr);
+            self.log_source_edits(table, vwr, cmd);
+            self.record_row_changes(cmd, table.rows.len());
+        }
+    }
+
+    /// Records modification of rows made by the command, to be delivered to the source.
+    fn log_source_edits<V: RowViewer<R>>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        cmd: &Command<R>,
+    ) {
+        let Some(edits) = self.source_edits.as_mut() else {
+            return;
+        };
+
+        let mut set_row = |row: RowIdx| {
+            UserCommand::SetRowValue(row.0, vwr.clone_row(&table.rows[row.0]).into())
+        };
+
+        match cmd {
+            Command::SetRowValue(row, _) => edits.push(set_row(*row)),
+            Command::SetCells { values, .. } => {
+                edits.extend(values.iter().map(|(row, ..)| *row).unique().map(set_row));
+            }
+            Command::InsertRows(pos, values) => {
+                let rows = (pos.0..pos.0 + values.len())
+                    .map(|x| vwr.clone_row(&table.rows[x]))
+                    .collect();
+
+                edits.push(UserCommand::InsertRows(pos.0, rows));
+            }
+            Command::RemoveRow(indices) => {
+                edits.push(UserCommand::RemoveRows(indices.iter().map(|x| x.0).collect()));
+            }
+            Command::MoveRows { from, to } => {
+                // Moved rows are inserted one by one in ascending order, after removing all of
+                // them; then every row lands on its final position.
+                edits.push(UserCommand::RemoveRows(from.iter().map(|x| x.0).collect()));
+                edits.extend(to.iter().map(|x| {
+                    UserCommand::InsertRows(x.0, [vwr.clone_row(&table.rows[x.0])].into())
+                }));
+            }
+            _ => {}
+        }
     }
 
     fn queue_select_rows(&mut self, rows: impl IntoIterator<Item = RowIdx>) {
@@ -1235,6 +3187,7 @@ impl<R> UiState<R> {
             self.undo_cursor += 1;
         }
         self.undo_queue = queue;
+        self.undo_generation += 1;
 
         true
     }
@@ -1247,495 +3200,2073 @@ impl<R> UiState<R> {
         let queue = take(&mut self.undo_queue);
         {
             self.undo_cursor -= 1;
-            self.cmd_apply(table, vwr, &queue[self.undo_cursor].apply);
+            for cmd in queue[self.undo_cursor].apply.iter() {
+                self.cmd_apply(table, vwr, cmd);
+            }
         }
         self.undo_queue = queue;
+        self.undo_generation += 1;
 
         true
     }
 
-    pub fn set_interactive_cell(&mut self, row: VisRowPos, col: VisColumnPos) {
-        self.cc_interactive_cell = row.linear_index(self.p.vis_cols.len(), col);
+    /// Translates groups of user commands into internal commands. Each group is recorded as a
+    /// single undo history entry.
+    pub fn translate_user_commands(groups: Vec<Vec<UserCommand<R>>>) -> Vec<Command<R>> {
+        groups
+            .into_iter()
+            .flat_map(|group| {
+                [Command::CcBeginUndoGroup]
+                    .into_iter()
+                    .chain(group.into_iter().map(Self::translate_user_command))
+                    .chain([Command::CcEndUndoGroup])
+            })
+            .collect()
     }
 
-    pub fn try_apply_ui_action(
+    fn translate_user_command(uc: UserCommand<R>) -> Command<R> {
+        match uc {
+            UserCommand::SetCells {
+                slab,
+                values,
+                context,
+            } => {
+                let values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]> = values
+                    .into_iter()
+                    .map(|(r, c, sidx)| (RowIdx(r), ColumnIdx(c), RowSlabIndex(sidx)))
+                    .collect();
+
+                match context {
+                    Some(ctx) => Command::CcSetCells {
+                        slab,
+                        values,
+                        context: ctx,
+                    },
+                    None => Command::SetCells { slab, values },
+                }
+            }
+            UserCommand::SetRowValue(r, v) => Command::SetRowValue(RowIdx(r), v),
+            UserCommand::InsertRows(pos, rows) => Command::InsertRows(RowIdx(pos), rows),
+            UserCommand::RemoveRows(mut rows) => {
+                // Removal requires sorted, unique indices.
+                rows.sort_unstable();
+                rows.dedup();
+                Command::RemoveRow(rows.into_iter().map(RowIdx).collect())
+            }
+        }
+    }
+
+    /// Pushes user commands as if they were issued from UI; i.e. every command is recorded in
+    /// undo history, and viewer callbacks are invoked.
+    pub fn apply_user_commands<V: RowViewer<R>>(
         &mut self,
         table: &mut DataTable<R>,
-        vwr: &mut impl RowViewer<R>,
-        action: UiAction,
-    ) -> Vec<Command<R>> {
-        fn empty<T, R>(_: T) -> Vec<Command<R>> {
-            default()
+        vwr: &mut V,
+        groups: Vec<Vec<UserCommand<R>>>,
+    ) {
+        for cmd in Self::translate_user_commands(groups) {
+            self.push_new_command(table, vwr, cmd, self.undo_capacity);
         }
+    }
 
-        self.cci_want_move_scroll = true;
+    /// Discards ongoing edition, if any, without committing it.
+    pub fn cancel_edition(&mut self) {
+        self.try_take_edition();
+    }
 
-        let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-    match action {
-            UiAction::SelectionStartEditing => {
-                let row_id = self.cc_rows[ic_r.0];
-                let src_row = &table.rows[row_id.0];
-                if vwr.is_editable_cell(ic_c.0, ic_r.0, &src_row) {
-                    let row = vwr.clone_row(src_row);
-                    vec![Command::CcEditStart(row_id, ic_c, Box::new(row))]
-                } else {
-                    vec![]
-                }
-            }
-            UiAction::Custom(action_id) => {
-                // Build selection snapshot for the callback
-                let selection_snapshot = {
-                    let mut selected_rows = Vec::new();
-                    if let Some(sels) = self.cursor_as_selection() {
-                        let mut rows = BTreeSet::new();
-                        for sel in sels.iter() {
-                            let (top, _) = sel.0.row_col(self.p.vis_cols.len());
-                            let (bottom, _) = sel.1.row_col(self.p.vis_cols.len());
-                            for r in top.0..=bottom.0 { rows.insert(r); }
-                        }
-                        for r in rows { let row_id = self.cc_rows[r].0; selected_rows.push((row_id, &table.rows[row_id])); }
-                    }
+    pub fn clear_undo_history(&mut self) {
+        self.undo_queue.clear();
+        self.undo_cursor = 0;
+        self.undo_group_has_entry = false;
+        self.undo_generation += 1;
+    }
 
-                    let mut selected_cells = Vec::new();
-                    if let Some(sels) = self.cursor_as_selection() {
-                        for sel in sels.iter() {
-                            let (top, left) = sel.0.row_col(self.p.vis_cols.len());
-                            let (bottom, right) = sel.1.row_col(self.p.vis_cols.len());
-                            for r in top.0..=bottom.0 {
-                                for c in left.0..=right.0 {
-                                    let row_id = self.cc_rows[r].0;
-                                    let col = self.p.vis_cols[c].0;
-                                    selected_cells.push((row_id, col));
-                                }
-                            }
-                        }
-                    }
+    pub fn export_undo_history(&self, rows: RowsFingerprint) -> UndoHistory<R>
+    where
+        R: Clone,
+    {
+        UndoHistory {
+            entries: self.undo_queue.iter().cloned().collect(),
+            cursor: self.undo_cursor,
+            rows,
+        }
+    }
 
-                    let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-                    let interactive_cell = if self.cc_rows.is_empty() { None } else { Some((self.cc_rows[ic_r.0].0, self.p.vis_cols[ic_c.0].0)) };
+    /// Replaces undo history. Fails if the history was taken from different rows, as row
+    /// indices would be invalid.
+    pub fn restore_undo_history(&mut self, history: UndoHistory<R>, rows: RowsFingerprint) -> bool {
+        if history.rows != rows || history.cursor > history.entries.len() {
+            return false;
+        }
 
-                    crate::viewer::SelectionSnapshot {
-                        selected_rows,
-                        selected_cells,
-                        interactive_cell,
-                        visible_columns: self.p.vis_cols.len(),
-                    }
-                };
+        self.undo_queue = history.entries.into();
+        self.undo_cursor = history.cursor;
+        self.undo_group_has_entry = false;
+        self.undo_generation += 1;
+        true
+    }
 
-                // Provide origin_cell (the interactive cell at the moment)
-                let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-                let origin_cell = if self.cc_rows.is_empty() {
-                    None
-                } else {
-                    Some((self.cc_rows[ic_r.0].0, self.p.vis_cols[ic_c.0].0))
-                };
+    /// Recomputes footer contents, only if rows, columns, data or selection changed since the
+    /// last call. Aggregates are computed over selected cells when more than one cell is
+    /// selected, or every visible row otherwise.
+    pub fn validate_footer(&mut self, table: &DataTable<R>, vwr: &mut impl RowViewer<R>) {
+        let key = AHasher::default().pipe(|mut hsh| {
+            (self.cc_generation, self.undo_generation, &self.p.vis_cols).hash(&mut hsh);
 
-                let ctx = crate::viewer::CustomActionContext { selection: selection_snapshot, origin_cell };
-                let mut editor = crate::viewer::CustomActionEditor::new();
-                vwr.on_custom_action_ex(action_id, &ctx, &mut editor);
-                let user_cmds = editor.into_commands();
-
-                // Translate user commands into internal Commands
-                user_cmds.into_iter().map(|uc| match uc {
-                    crate::viewer::UserCommand::SetCells { slab, values, context } => {
-                        let values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]> = values
-                            .into_iter()
-                            .map(|(r, c, sidx)| (RowIdx(r), ColumnIdx(c), RowSlabIndex(sidx)))
-                            .collect::<Vec<_>>()
-                            .into_boxed_slice();
-                        match context {
-                            Some(ctx) => Command::CcSetCells { slab, values, context: ctx },
-                            None => Command::SetCells { slab, values },
-                        }
-                    }
-                    crate::viewer::UserCommand::SetRowValue(r, v) => Command::SetRowValue(RowIdx(r), v),
-                    crate::viewer::UserCommand::InsertRows(pos, rows) => Command::InsertRows(RowIdx(pos), rows),
-                    crate::viewer::UserCommand::RemoveRows(rows) => Command::RemoveRow(rows.into_iter().map(RowIdx).collect()),
-                }).collect()
+            if let CursorState::Select(selections) = &self.cc_cursor {
+                selections.hash(&mut hsh);
             }
-            UiAction::CancelEdition => vec![Command::CcCancelEdit],
-            UiAction::CommitEdition => vec![Command::CcCommitEdit],
-            UiAction::CommitEditionAndMove(dir) => {
-                let pos = self.moved_position(self.cc_interactive_cell, dir);
-                let (r, c) = pos.row_col(self.p.vis_cols.len());
 
-                let mut commands = vec![
-                    Command::CcCommitEdit,
-                ];
-                
-                let row_id = self.cc_rows[r.0];
-                if vwr.is_editable_cell(c.0, r.0, &table.rows[row_id.0]) {
-                    let row_value = if self.is_editing() && ic_r == r {
-                        vwr.clone_row(self.unwrap_editing_row_data())
-                    } else {
-                        vwr.clone_row(&table.rows[row_id.0])
-                    };
-                    
-                    commands.push(Command::CcEditStart(row_id, c, row_value.into()));
-                }
-                
-                commands
-            }
-            UiAction::MoveSelection(dir) => {
-                let pos = self.moved_position(self.cc_interactive_cell, dir);
-                vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
-            }
-            UiAction::Undo => self.undo(table, vwr).pipe(empty),
-            UiAction::Redo => self.redo(table, vwr).pipe(empty),
-            UiAction::CopySelection | UiAction::CutSelection => {
-                let sels = self.collect_selection();
-                self.clipboard = None;
+            hsh.finish()
+        });
 
-                if sels.is_empty() {
-                    return vec![]; // we do nothing.
-                }
+        if self.cc_footer.as_ref().is_some_and(|x| x.key == key) {
+            return;
+        }
 
-                // Copy contents to clipboard
-                let offset = sels.first().unwrap().0;
-                let mut slab = Vec::with_capacity(10);
-                let mut vis_map = HashMap::with_capacity(10);
+        let selection = self.collect_selection();
+        let is_selection = selection.len() > 1;
+        let num_vis_cols = self.p.vis_cols.len();
 
-                for vis_row in self.collect_selected_rows() {
-                    vis_map.insert(vis_row, slab.len());
-                    slab.push(vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[vis_row.0].0]));
-                }
+        let mut column_rows = vec![Vec::new(); num_vis_cols];
+        if is_selection {
+            for (vis_row, vis_col) in &selection {
+                column_rows[vis_col.0].push(&table.rows[self.cc_rows[vis_row.0].0]);
+            }
+        } else {
+            let rows = self.cc_rows.iter().map(|x| &table.rows[x.0]).collect_vec();
+            column_rows.fill(rows);
+        }
 
-                let clipboard = Clipboard {
-                    slab: slab.into_boxed_slice(),
-                    pastes: sels
-                        .iter()
-                        .map(|(v_r, v_c)| {
-                            (
-                                VisRowOffset(v_r.0 - offset.0),
-                                self.p.vis_cols[v_c.0],
-                                RowSlabIndex(vis_map[v_r]),
-                            )
-                        })
-                        .collect(),
-                }
-                .tap_mut(Clipboard::sort);
+        let aggregates = column_rows
+            .iter()
+            .zip(&self.p.vis_cols)
+            .map(|(rows, column)| vwr.column_aggregate(rows, column.0))
+            .collect();
 
-                let sys_clip = Self::try_dump_clipboard_content(&clipboard, vwr);
-                self.clipboard = Some(clipboard);
+        self.cc_footer = Some(FooterCache {
+            key,
+            aggregates,
+            is_selection,
+            num_selected_cells: selection.len(),
+            num_selected_rows: selection.iter().map(|(row, _)| row).dedup().count(),
+        });
+    }
 
-                if action == UiAction::CutSelection {
-                    self.try_apply_ui_action(table, vwr, UiAction::DeleteSelection)
-                } else {
-                    vec![]
-                }
-                .tap_mut(|v| {
-                    // We only overwrite system clipboard when codec support is active.
-                    if let Some(clip) = sys_clip {
-                        v.push(Command::CcUpdateSystemClipboard(clip));
-                    }
-                })
-            }
-            UiAction::SelectionDuplicateValues => {
-                let pivot_row = vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[ic_r.0].0]);
-                let sels = self.collect_selection();
+    /// Footer contents; valid only after [`Self::validate_footer`] is called.
+    pub fn footer(&self) -> Option<&FooterCache> {
+        self.cc_footer.as_ref()
+    }
 
-                vec![Command::CcSetCells {
-                    slab: [pivot_row].into(),
-                    values: sels
-                        .into_iter()
-                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
-                        .collect(),
-                    context: CellWriteContext::Paste,
-                }]
-            }
-            UiAction::PasteInPlace => {
-                let Some(clip) = &self.clipboard else {
-                    return vec![];
-                };
+    /// Search texts of given cells, in order.
+    fn cell_search_texts(
+        &self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        cells: &[VisLinearIdx],
+    ) -> Vec<Option<String>> {
+        let num_cols = self.p.vis_cols.len();
+        let cell = |idx: &VisLinearIdx| {
+            let (r, c) = idx.row_col(num_cols);
+            (&table.rows[self.cc_rows[r.0].0], self.p.vis_cols[c.0])
+        };
 
-                let values =
-                    Vec::from_iter(clip.pastes.iter().filter_map(|(offset, col, slab_id)| {
-                        let vis_r = VisRowPos(ic_r.0 + offset.0);
-                        (vis_r.0 < self.cc_rows.len())
-                            .then(|| (self.cc_rows[vis_r.0], *col, *slab_id))
-                    }));
+        let encoded = encode_cells(vwr, cells.iter().map(cell));
 
-                let desired = self.cc_desired_selection.get_or_insert(default());
-                desired.clear();
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, idx)| {
+                let (row, column) = cell(idx);
+                let text = encoded.as_ref().map(|texts| texts[i].as_str());
+                vwr.cell_search_text(row, column.0, text)
+            })
+            .collect()
+    }
 
-                for (row, group) in &values.iter().chunk_by(|(row, ..)| *row) {
-                    desired.push((row, group.map(|(_, c, ..)| *c).collect()))
-                }
+    /// Moves interactive cell to the next matching cell in display order, wrapping around.
+    pub fn find_next(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        backward: bool,
+    ) -> Vec<Command<R>> {
+        let num_cols = self.p.vis_cols.len();
+        let num_cells = self.cc_rows.len() * num_cols;
 
-                vec![Command::CcSetCells {
-                    slab: clip.slab.iter().map(|x| vwr.clone_row(x)).collect(),
-                    values: values.into_boxed_slice(),
-                    context: CellWriteContext::Paste,
-                }]
-            }
-            UiAction::PasteInsert => {
-                let Some(clip) = &self.clipboard else {
-                    return vec![];
-                };
+        if num_cells == 0 {
+            self.find.no_match = true;
+            return vec![];
+        }
 
-                let mut last = usize::MAX;
-                let mut rows = clip
-                    .pastes
-                    .iter()
-                    .filter(|&(offset, ..)| replace(&mut last, offset.0) != offset.0)
-                    .map(|(offset, ..)| {
-                        (
-                            *offset,
-                            vwr.new_empty_row_for(EmptyRowCreateContext::InsertNewLine),
-                        )
+        // Cells are searched in chunks, so that the next match nearby doesn't require encoding
+        // every cell.
+        const CHUNK_SIZE: usize = 1024;
+
+        let start = self.cc_interactive_cell.0.min(num_cells - 1);
+        let found = (1..=num_cells)
+            .map(|step| match backward {
+                true => (start + num_cells - step) % num_cells,
+                false => (start + step) % num_cells,
+            })
+            .map(VisLinearIdx)
+            .chunks(CHUNK_SIZE)
+            .into_iter()
+            .find_map(|chunk| {
+                let cells = chunk.collect_vec();
+                let texts = self.cell_search_texts(table, vwr, &cells);
+
+                cells.into_iter().zip(texts).find_map(|(idx, text)| {
+                    text.filter(|text| {
+                        find::find_text(text, &self.find.query, self.find.match_case).is_some()
                     })
-                    .collect::<BTreeMap<_, _>>();
+                    .map(|_| idx)
+                })
+            });
 
-                for (offset, column, slab_id) in &*clip.pastes {
-                    vwr.set_cell_value(
-                        &clip.slab[slab_id.0],
-                        rows.get_mut(offset).unwrap(),
-                        column.0,
-                    );
-                }
+        self.find.no_match = found.is_none();
 
-                let pos = if self.p.sort.is_empty() {
-                    self.cc_rows[ic_r.0]
-                } else {
-                    RowIdx(table.rows.len())
+        let Some(idx) = found else {
+            return vec![];
+        };
+
+        self.cci_want_move_scroll = true;
+        vec![Command::CcSetSelection(vec![VisSelection(idx, idx)])]
+    }
+
+    /// Replaces occurrences of the query in the interactive cell then moves to the next match,
+    /// or in every visible cell if `all` is set. Changes are made as a single `SetCells`
+    /// command.
+    pub fn find_replace(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        all: bool,
+    ) -> Vec<Command<R>> {
+        let num_cols = self.p.vis_cols.len();
+        let cells = if all {
+            (0..self.cc_rows.len() * num_cols).map(VisLinearIdx).collect_vec()
+        } else if self.cc_rows.is_empty() {
+            vec![]
+        } else {
+            vec![self.cc_interactive_cell]
+        };
+
+        // Collect replaced texts first, as the codec borrows the viewer while decoding.
+        let mut targets = Vec::new();
+        let texts = self.cell_search_texts(table, vwr, &cells);
+
+        for (idx, text) in cells.into_iter().zip(texts) {
+            let (r, c) = idx.row_col(num_cols);
+            let (row_id, column) = (self.cc_rows[r.0], self.p.vis_cols[c.0]);
+            let row = &table.rows[row_id.0];
+
+            let Some(text) = text else {
+                continue;
+            };
+
+            if let Some(text) = find::replace_text(
+                &text,
+                &self.find.query,
+                &self.find.replacement,
+                self.find.match_case,
+            ) {
+                targets.push((row_id, column, text, vwr.clone_row(row)));
+            }
+        }
+
+        let mut slab = Vec::with_capacity(targets.len());
+        let mut values = Vec::with_capacity(targets.len());
+
+        if !targets.is_empty()
+            && let Some(mut codec) = vwr.try_create_codec(false)
+        {
+            for (row_id, column, text, mut dst) in targets {
+                if codec.decode_column(&text, column.0, &mut dst).is_ok() {
+                    values.push((row_id, column, RowSlabIndex(slab.len())));
+                    slab.push(dst);
+                }
+            }
+        }
+
+        let mut commands = Vec::new();
+
+        if !values.is_empty() {
+            commands.push(Command::CcSetCells {
+                slab: slab.into_boxed_slice(),
+                values: values.into_boxed_slice(),
+                context: CellWriteContext::Replace,
+            });
+        }
+
+        if !all {
+            commands.extend(self.find_next(table, vwr, false));
+        } else {
+            self.find.no_match = commands.is_empty();
+        }
+
+        commands
+    }
+
+    /// Whether rows are shown in the order of the table, thus can be moved by dragging.
+    pub fn can_reorder_rows(&self) -> bool {
+        self.p.sort.is_empty() && self.p.group_by.is_none()
+    }
+
+    pub fn is_row_selected(&self, row: VisRowPos) -> bool {
+        let ncol = self.p.vis_cols.len();
+        self.cursor_as_selection().is_some_and(|sels| {
+            sels.iter().any(|sel| {
+                let (top, _) = sel.0.row_col(ncol);
+                let (bottom, _) = sel.1.row_col(ncol);
+                (top..=bottom).contains(&row)
+            })
+        })
+    }
+
+    /// Moves every selected row right before, or after the `target` row.
+    pub fn move_selected_rows(&mut self, target: VisRowPos, after: bool) -> Vec<Command<R>> {
+        let ncol = self.p.vis_cols.len();
+        let Some(sels) = self.cursor_as_selection() else {
+            return vec![];
+        };
+
+        let from = sels
+            .iter()
+            .flat_map(|sel| sel.0.row_col(ncol).0 .0..=sel.1.row_col(ncol).0 .0)
+            .map(|vis_row| self.cc_rows[vis_row])
+            .sorted()
+            .dedup()
+            .collect_vec();
+
+        let before = self.cc_rows[target.0].0 + after as usize;
+
+        // Position among the rows which are not moved.
+        let pos = before - from.partition_point(|row_id| row_id.0 < before);
+        let to = (pos..pos + from.len()).map(RowIdx).collect_vec();
+
+        if from.is_empty() || from == to {
+            return vec![];
+        }
+
+        vec![Command::MoveRows { from, to }]
+    }
+
+    /// Source of the fill handle; the selection, if it's a single rectangle.
+    pub fn fill_source(&self) -> Option<VisSelection> {
+        match self.cursor_as_selection()? {
+            [sel] => Some(*sel),
+            _ => None,
+        }
+    }
+
+    pub fn is_filling(&self) -> bool {
+        self.cci_fill_target.is_some()
+    }
+
+    pub fn begin_fill(&mut self) {
+        let ncol = self.p.vis_cols.len();
+        self.cci_fill_target = self.fill_source().map(|sel| sel.1.row_col(ncol));
+    }
+
+    pub fn update_fill_target(&mut self, row: VisRowPos, col: VisColumnPos) {
+        if let Some(target) = &mut self.cci_fill_target {
+            *target = (row, col);
+        }
+    }
+
+    fn fill_area(&self) -> Option<FillArea> {
+        let (row, col) = self.cci_fill_target?;
+        let ncol = self.p.vis_cols.len();
+        let source = self.fill_source()?;
+        let (top, left) = source.0.row_col(ncol);
+        let (bottom, right) = source.1.row_col(ncol);
+
+        let d_row = row.0.saturating_sub(bottom.0).max(top.0.saturating_sub(row.0));
+        let d_col = col.0.saturating_sub(right.0).max(left.0.saturating_sub(col.0));
+
+        if d_row == 0 && d_col == 0 {
+            return None;
+        }
+
+        // Like spreadsheets, extends along the axis the pointer went further.
+        let vertical = d_row >= d_col;
+
+        Some(FillArea {
+            source,
+            vertical,
+            forward: if vertical { row > bottom } else { col > right },
+            len: d_row.max(d_col),
+        })
+    }
+
+    /// Whether the cell is going to be filled when the fill handle is released.
+    pub fn is_fill_target(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        let ncol = self.p.vis_cols.len();
+        self.fill_area()
+            .is_some_and(|area| area.filled(ncol).contains(ncol, row, col))
+    }
+
+    /// Releases the fill handle, filling the cells through [`RowViewer::fill_series`].
+    pub fn finish_fill(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+    ) -> Vec<Command<R>> {
+        let area = self.fill_area();
+        self.cci_fill_target = None;
+
+        let Some(area) = area else {
+            return vec![];
+        };
+
+        let ncol = self.p.vis_cols.len();
+        let (top, left) = area.source.0.row_col(ncol);
+        let (bottom, right) = area.source.1.row_col(ncol);
+
+        // Each line runs along the fill direction; a column of vertical fill, or a row otherwise.
+        let (lines, positions) = match area.vertical {
+            true => (left.0..=right.0, top.0..=bottom.0),
+            false => (top.0..=bottom.0, left.0..=right.0),
+        };
+        let cell = |line: usize, pos: usize| match area.vertical {
+            true => (self.cc_rows[pos], self.p.vis_cols[line]),
+            false => (self.cc_rows[line], self.p.vis_cols[pos]),
+        };
+
+        let mut slab = Vec::new();
+        let mut values = Vec::new();
+
+        for line in lines {
+            let mut sources = positions
+                .clone()
+                .map(|pos| cell(line, pos))
+                .map(|(row_id, col)| (&table.rows[row_id.0], col.0))
+                .collect_vec();
+
+            if !area.forward {
+                sources.reverse();
+            }
+
+            for offset in 0..area.len {
+                let pos = match area.forward {
+                    true => positions.end() + 1 + offset,
+                    false => positions.start() - 1 - offset,
+                };
+                let (row_id, column) = cell(line, pos);
+
+                let fill = FillSeries {
+                    sources: &sources,
+                    index: sources.len() + offset,
+                    target: &table.rows[row_id.0],
+                    column: column.0,
+                };
+
+                if let Some(value) = vwr.fill_series(&fill) {
+                    values.push((row_id, column, RowSlabIndex(slab.len())));
+                    slab.push(value);
+                }
+            }
+        }
+
+        if values.is_empty() {
+            return vec![];
+        }
+
+        // Select both the source and the filled cells.
+        let sel = area.source.union(ncol, area.filled(ncol));
+        let (top, left) = sel.0.row_col(ncol);
+        let (bottom, right) = sel.1.row_col(ncol);
+        let columns = self.p.vis_cols[left.0..=right.0].to_vec();
+
+        self.cc_desired_selection = Some(
+            (top.0..=bottom.0)
+                .map(|r| (self.cc_rows[r], columns.clone()))
+                .collect(),
+        );
+
+        vec![Command::CcSetCells {
+            slab: slab.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+            context: CellWriteContext::Fill,
+        }]
+    }
+
+    /// Loads undo history from egui memory on the first call, then stores it back whenever it
+    /// changes.
+    #[cfg(feature = "persistency")]
+    pub fn validate_undo_persistency<V: RowViewer<R>>(
+        &mut self,
+        ctx: &egui::Context,
+        id: egui::Id,
+        rows: &[R],
+        vwr: &mut V,
+    ) where
+        R: serde::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        match self.undo_p_generation {
+            None => {
+                if !self.has_undo() && !self.has_redo() {
+                    let history: Option<UndoHistory<R>> =
+                        ctx.memory_mut(|m| m.data.get_persisted(id));
+
+                    if let Some(history) = history {
+                        self.restore_undo_history(history, RowsFingerprint::new(rows, vwr));
+                    }
+                }
+
+                self.undo_p_generation = Some(self.undo_generation);
+            }
+            Some(generation) if generation != self.undo_generation => {
+                let history = self.export_undo_history(RowsFingerprint::new(rows, vwr));
+                ctx.memory_mut(|m| m.data.insert_persisted(id, history));
+
+                self.undo_p_generation = Some(self.undo_generation);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Lists undo history from the most recent one, including undone entries.
+    pub fn history_entries(&self) -> Vec<HistoryEntry> {
+        self.undo_queue
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let describe = |cmd: &Command<R>| match cmd {
+                    Command::SetCells { values, .. } => {
+                        Some((HistoryEntryKind::SetCells, values.len()))
+                    }
+                    Command::SetRowValue(..) => Some((HistoryEntryKind::SetRow, 1)),
+                    Command::InsertRows(_, rows) => {
+                        Some((HistoryEntryKind::InsertRows, rows.len()))
+                    }
+                    Command::RemoveRow(rows) => Some((HistoryEntryKind::RemoveRows, rows.len())),
+                    Command::MoveRows { from, .. } => {
+                        Some((HistoryEntryKind::MoveRows, from.len()))
+                    }
+                    Command::SetColumnSort(sort) => {
+                        Some((HistoryEntryKind::SetColumnSort, sort.len()))
+                    }
+                    Command::SetVisibleColumns(cols) => {
+                        Some((HistoryEntryKind::SetVisibleColumns, cols.len()))
+                    }
+                    _ => None,
                 };
 
-                let row_values = rows.into_values().collect();
-                vec![Command::InsertRows(pos, row_values)]
-            }
-            UiAction::DuplicateRow => {
-                if vwr.allow_row_insertions() {
-                    let rows = self
-                        .collect_selected_rows()
-                        .into_iter()
-                        .map(|x| self.cc_rows[x.0])
-                        .map(|r| vwr.clone_row_for_insertion(&table.rows[r.0]))
-                        .collect();
+                // Grouped entries are described by their first command.
+                let (kind, _) = item.apply.iter().find_map(describe)?;
+                let count = item
+                    .apply
+                    .iter()
+                    .filter_map(describe)
+                    .filter(|(k, _)| *k == kind)
+                    .map(|(_, count)| count)
+                    .sum();
+
+                Some(HistoryEntry {
+                    kind,
+                    count,
+                    is_undone: index < self.undo_cursor,
+                })
+            })
+            .collect()
+    }
+
+    pub fn set_interactive_cell(&mut self, row: VisRowPos, col: VisColumnPos) {
+        self.cc_interactive_cell = row.linear_index(self.p.vis_cols.len(), col);
+    }
+
+    /// Snapshot of the selection, which is handed to custom actions of the viewer.
+    pub(crate) fn selection_snapshot<'a>(
+        &self,
+        table: &'a DataTable<R>,
+    ) -> SelectionSnapshot<'a, R> {
+        let row_id = |r: VisRowPos| self.cc_rows[r.0].0;
+
+        SelectionSnapshot {
+            selected_rows: self
+                .collect_selected_rows()
+                .into_iter()
+                .map(|r| (row_id(r), &table.rows[row_id(r)]))
+                .collect(),
+            selected_cells: self
+                .collect_selection()
+                .into_iter()
+                .map(|(r, c)| (row_id(r), self.p.vis_cols[c.0].0))
+                .collect(),
+            interactive_cell: (!self.cc_rows.is_empty()).then(|| {
+                let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+                (row_id(r), self.p.vis_cols[c.0].0)
+            }),
+            visible_columns: self.p.vis_cols.len(),
+        }
+    }
+
+    /// Dispatches a custom action to [`RowViewer::on_custom_action_ex`].
+    pub fn custom_action_commands(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        action_id: &'static str,
+        origin: CustomActionOrigin,
+    ) -> Vec<Command<R>> {
+        let selection = self.selection_snapshot(table);
+        let origin_cell = selection.interactive_cell;
+        let ctx = crate::viewer::CustomActionContext { selection, origin_cell, origin };
+        let mut editor = crate::viewer::CustomActionEditor::new();
+        vwr.on_custom_action_ex(action_id, &ctx, &mut editor);
+        Self::translate_user_commands(editor.into_command_groups())
+    }
+
+    pub fn try_apply_ui_action(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        action: UiAction,
+    ) -> Vec<Command<R>> {
+        fn empty<T, R>(_: T) -> Vec<Command<R>> {
+            default()
+        }
+
+        self.cci_want_move_scroll = true;
+
+        let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+    match action {
+            UiAction::SelectionStartEditing => {
+                let row_id = self.cc_rows[ic_r.0];
+                let src_row = &table.rows[row_id.0];
+                if vwr.is_editable_cell(ic_c.0, ic_r.0, src_row) {
+                    let row = vwr.clone_row(src_row);
+                    vec![Command::CcEditStart(row_id, ic_c, Box::new(row))]
+                } else {
+                    vec![]
+                }
+            }
+            UiAction::Custom(action_id) => {
+                self.custom_action_commands(table, vwr, action_id, CustomActionOrigin::Cell)
+            }
+            UiAction::CancelEdition => vec![Command::CcCancelEdit],
+            UiAction::CommitEdition => vec![Command::CcCommitEdit],
+            UiAction::CommitEditionAndMove(dir) => {
+                let pos = self.moved_position(self.cc_interactive_cell, dir);
+                let (r, c) = pos.row_col(self.p.vis_cols.len());
+
+                let mut commands = vec![
+                    Command::CcCommitEdit,
+                ];
+                
+                let row_id = self.cc_rows[r.0];
+                if vwr.is_editable_cell(c.0, r.0, &table.rows[row_id.0]) {
+                    let row_value = if self.is_editing() && ic_r == r {
+                        vwr.clone_row(self.unwrap_editing_row_data())
+                    } else {
+                        vwr.clone_row(&table.rows[row_id.0])
+                    };
+                    
+                    commands.push(Command::CcEditStart(row_id, c, row_value.into()));
+                }
+                
+                commands
+            }
+            UiAction::MoveSelection(dir) => {
+                let pos = self.moved_position(self.cc_interactive_cell, dir);
+                vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
+            }
+            UiAction::Undo => self.undo(table, vwr).pipe(empty),
+            UiAction::Redo => self.redo(table, vwr).pipe(empty),
+            UiAction::CopySelection | UiAction::CutSelection => {
+                let sels = self.collect_selection();
+                self.clipboard = None;
+
+                if sels.is_empty() {
+                    return vec![]; // we do nothing.
+                }
+
+                // Copy contents to clipboard
+                let offset = sels.first().unwrap().0;
+                let mut slab = Vec::with_capacity(10);
+                let mut vis_map = HashMap::with_capacity(10);
+
+                for vis_row in self.collect_selected_rows() {
+                    vis_map.insert(vis_row, slab.len());
+                    slab.push(vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[vis_row.0].0]));
+                }
+
+                let clipboard = Clipboard {
+                    slab: slab.into_boxed_slice(),
+                    pastes: sels
+                        .iter()
+                        .map(|(v_r, v_c)| {
+                            (
+                                VisRowOffset(v_r.0 - offset.0),
+                                self.p.vis_cols[v_c.0],
+                                RowSlabIndex(vis_map[v_r]),
+                            )
+                        })
+                        .collect(),
+                }
+                .tap_mut(Clipboard::sort);
+
+                let sys_clip =
+                    Self::try_dump_clipboard_content(&clipboard, vwr, self.clipboard_format);
+                self.clipboard = Some(clipboard);
+
+                if action == UiAction::CutSelection {
+                    self.try_apply_ui_action(table, vwr, UiAction::DeleteSelection)
+                } else {
+                    vec![]
+                }
+                .tap_mut(|v| {
+                    // We only overwrite system clipboard when codec support is active.
+                    if let Some(clip) = sys_clip {
+                        v.push(Command::CcUpdateSystemClipboard(clip));
+                    }
+                })
+            }
+            UiAction::SelectionDuplicateValues => {
+                let pivot_row = vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[ic_r.0].0]);
+                let sels = self.collect_selection();
+
+                vec![Command::CcSetCells {
+                    slab: [pivot_row].into(),
+                    values: sels
+                        .into_iter()
+                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
+                        .collect(),
+                    context: CellWriteContext::Paste,
+                }]
+            }
+            UiAction::PasteInPlace => {
+                let Some(clip) = &self.clipboard else {
+                    return vec![];
+                };
+
+                let values =
+                    Vec::from_iter(clip.pastes.iter().filter_map(|(offset, col, slab_id)| {
+                        let vis_r = VisRowPos(ic_r.0 + offset.0);
+                        (vis_r.0 < self.cc_rows.len())
+                            .then(|| (self.cc_rows[vis_r.0], *col, *slab_id))
+                    }));
+
+                let desired = self.cc_desired_selection.get_or_insert(default());
+                desired.clear();
+
+                for (row, group) in &values.iter().chunk_by(|(row, ..)| *row) {
+                    desired.push((row, group.map(|(_, c, ..)| *c).collect()))
+                }
+
+                vec![Command::CcSetCells {
+                    slab: clip.slab.iter().map(|x| vwr.clone_row(x)).collect(),
+                    values: values.into_boxed_slice(),
+                    context: CellWriteContext::Paste,
+                }]
+            }
+            UiAction::PasteInsert => {
+                let Some(clip) = &self.clipboard else {
+                    return vec![];
+                };
+
+                let mut last = usize::MAX;
+                let mut rows = clip
+                    .pastes
+                    .iter()
+                    .filter(|&(offset, ..)| replace(&mut last, offset.0) != offset.0)
+                    .map(|(offset, ..)| {
+                        (
+                            *offset,
+                            vwr.new_empty_row_for(EmptyRowCreateContext::InsertNewLine),
+                        )
+                    })
+                    .collect::<BTreeMap<_, _>>();
+
+                for (offset, column, slab_id) in &*clip.pastes {
+                    vwr.set_cell_value(
+                        &clip.slab[slab_id.0],
+                        rows.get_mut(offset).unwrap(),
+                        column.0,
+                    );
+                }
+
+                let pos = if self.p.sort.is_empty() {
+                    self.cc_rows[ic_r.0]
+                } else {
+                    RowIdx(table.rows.len())
+                };
+
+                let row_values = rows.into_values().collect();
+                vec![Command::InsertRows(pos, row_values)]
+            }
+            UiAction::DuplicateRow => {
+                if vwr.allow_row_insertions() {
+                    let rows = self
+                        .collect_selected_rows()
+                        .into_iter()
+                        .map(|x| self.cc_rows[x.0])
+                        .map(|r| vwr.clone_row_for_insertion(&table.rows[r.0]))
+                        .collect();
+
+                    let pos = if self.p.sort.is_empty() {
+                        self.cc_rows[ic_r.0]
+                    } else {
+                        RowIdx(table.rows.len())
+                    };
+
+                    vec![Command::InsertRows(pos, rows)]
+                } else {
+                    vec![]
+                }
+            }
+            UiAction::DeleteSelection => {
+                let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
+                let sels = self.collect_selection();
+                let slab = vec![default].into_boxed_slice();
+
+                vec![Command::CcSetCells {
+                    slab,
+                    values: sels
+                        .into_iter()
+                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
+                        .collect(),
+                    context: CellWriteContext::Clear,
+                }]
+            }
+            UiAction::DeleteRow => {
+                if vwr.allow_row_deletions() {
+                    let rows = self
+                        .collect_selected_rows()
+                        .into_iter()
+                        .map(|x| self.cc_rows[x.0])
+                        .filter(|row| vwr.confirm_row_deletion_by_ui(&table.rows[row.0]))
+                        .collect();
+
+                    vec![Command::RemoveRow(rows)]
+                } else {
+                    vec![]
+                }
+            }
+            UiAction::Find => {
+                self.find.is_open = true;
+                self.find.want_focus = true;
+                vec![]
+            }
+            UiAction::SelectAll => {
+                if self.cc_rows.is_empty() {
+                    return vec![];
+                }
+
+                vec![Command::CcSetSelection(vec![VisSelection(
+                    VisLinearIdx(0),
+                    VisRowPos(self.cc_rows.len().saturating_sub(1)).linear_index(
+                        self.p.vis_cols.len(),
+                        VisColumnPos(self.p.vis_cols.len() - 1),
+                    ),
+                )])]
+            }
+
+            action @ (UiAction::NavPageDown
+            | UiAction::NavPageUp
+            | UiAction::NavTop
+            | UiAction::NavBottom) => {
+                let ofst = match action {
+                    UiAction::NavPageDown => self.cci_page_row_count as isize,
+                    UiAction::NavPageUp => -(self.cci_page_row_count as isize),
+                    UiAction::NavTop => isize::MIN,
+                    UiAction::NavBottom => isize::MAX,
+                    _ => unreachable!(),
+                };
+
+                let new_ic_r = (ic_r.0 as isize)
+                    .saturating_add(ofst)
+                    .clamp(0, self.cc_rows.len().saturating_sub(1) as _);
+                self.cc_interactive_cell =
+                    VisLinearIdx(new_ic_r as usize * self.p.vis_cols.len() + ic_c.0);
+
+                self.validate_interactive_cell(self.p.vis_cols.len());
+                vec![Command::CcSetSelection(vec![VisSelection(
+                    self.cc_interactive_cell,
+                    self.cc_interactive_cell,
+                )])]
+            }
+        }
+    }
+
+    fn collect_selection(&self) -> BTreeSet<(VisRowPos, VisColumnPos)> {
+        let mut set = BTreeSet::new();
+
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            for sel in selections.iter() {
+                let (top, left) = sel.0.row_col(self.p.vis_cols.len());
+                let (bottom, right) = sel.1.row_col(self.p.vis_cols.len());
+
+                for r in top.0..=bottom.0 {
+                    for c in left.0..=right.0 {
+                        set.insert((VisRowPos(r), VisColumnPos(c)));
+                    }
+                }
+            }
+        }
+
+        set
+    }
+
+    fn collect_selected_rows(&self) -> BTreeSet<VisRowPos> {
+        let mut rows = BTreeSet::new();
+
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            for sel in selections.iter() {
+                let (top, _) = sel.0.row_col(self.p.vis_cols.len());
+                let (bottom, _) = sel.1.row_col(self.p.vis_cols.len());
+
+                for r in top.0..=bottom.0 {
+                    rows.insert(VisRowPos(r));
+                }
+            }
+        }
+
+        rows
+    }
+
+    fn moved_position(&self, pos: VisLinearIdx, dir: MoveDirection) -> VisLinearIdx {
+        let (VisRowPos(r), VisColumnPos(c)) = pos.row_col(self.p.vis_cols.len());
+
+        let (rmax, cmax) = (
+            self.cc_rows.len().saturating_sub(1),
+            self.p.vis_cols.len().saturating_sub(1),
+        );
+
+        let (nr, nc) = match dir {
+            MoveDirection::Up => match (r, c) {
+                (0, c) => (0, c),
+                (r, c) => (r - 1, c),
+            },
+            MoveDirection::Down => match (r, c) {
+                (r, c) if r == rmax => (r, c),
+                (r, c) => (r + 1, c),
+            },
+            MoveDirection::Left => match (r, c) {
+                (0, 0) => (0, 0),
+                (r, 0) => (r - 1, cmax),
+                (r, c) => (r, c - 1),
+            },
+            MoveDirection::Right => match (r, c) {
+                (r, c) if r == rmax && c == cmax => (r, c),
+                (r, c) if c == cmax => (r + 1, 0),
+                (r, c) => (r, c + 1),
+            },
+        };
+
+        VisLinearIdx(nr * self.p.vis_cols.len() + nc)
+    }
+
+    pub fn cci_take_selection(&mut self, mods: egui::Modifiers) -> Option<Vec<VisSelection>> {
+        let ncol = self.p.vis_cols.len();
+        let cci_sel = self
+            .cci_selection
+            .take()
+            .map(|(_0, _1)| VisSelection::from_points(ncol, _0, _1))?;
+
+        if mods.is_none() {
+            return Some(vec![cci_sel]);
+        }
+
+        let mut sel = self.cursor_as_selection().unwrap_or_default().to_owned();
+        let idx_contains = sel.iter().position(|x| x.contains_rect(ncol, cci_sel));
+        if sel.is_empty() {
+            sel.push(cci_sel);
+            return Some(sel);
+        }
+
+        if mods.command_only() {
+            if let Some(idx) = idx_contains {
+                sel.remove(idx);
+            } else {
+                sel.push(cci_sel);
+            }
+        }
+
+        if mods.cmd_ctrl_matches(Modifiers::SHIFT) {
+            let last = sel.last_mut().unwrap();
+            if cci_sel.is_point() && last.is_point() {
+                *last = last.union(ncol, cci_sel);
+            } else if idx_contains.is_none() {
+                sel.push(cci_sel);
+            };
+        }
+
+        Some(sel)
+    }
+}
+
+/* ------------------------------------------ Commands ------------------------------------------ */
+
+/// NOTE: `Cc` prefix stands for cache command which won't be stored in undo/redo queue, since they
+/// are not called from `cmd_apply` method. Therefore they're never serialized.
+#[derive(Clone)]
+#[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum Command<R> {
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcHideColumn(ColumnIdx),
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcShowColumn {
+        what: ColumnIdx,
+        at: VisColumnPos,
+    },
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcReorderColumn {
+        from: VisColumnPos,
+        to: VisColumnPos,
+    },
+
+    SetColumnSort(Vec<(ColumnIdx, IsAscending)>),
+    SetVisibleColumns(Vec<ColumnIdx>),
+
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcSetSelection(Vec<VisSelection>), // Cache - Set Selection
+
+    SetRowValue(RowIdx, Box<R>),
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcSetCells {
+        slab: Box<[R]>,
+        values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]>,
+        context: CellWriteContext,
+    },
+    SetCells {
+        slab: Box<[R]>,
+        values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]>,
+    },
+
+    InsertRows(RowIdx, Box<[R]>),
+    RemoveRow(Vec<RowIdx>),
+
+    /// Moves rows at sorted indices `from` to sorted indices `to`, keeping their relative
+    /// order. Other rows fill the remaining indices in order. Swapping the two undoes the move.
+    MoveRows {
+        from: Vec<RowIdx>,
+        to: Vec<RowIdx>,
+    },
+
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcEditStart(RowIdx, VisColumnPos, Box<R>),
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcCancelEdit,
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcCommitEdit,
+
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcUpdateSystemClipboard(String),
+
+    /// Subsequent commands are merged into a single undo history entry, until the group is
+    /// ended. Groups don't nest; beginning a group ends the previous one.
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcBeginUndoGroup,
+    #[cfg_attr(feature = "persistency", serde(skip))]
+    CcEndUndoGroup,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{CellStyle, CellValidation};
+
+    /// Sorts rows by tens, so equal rows are kept in storage order, and hides multiples of 7.
+    /// Cells are encoded as decimal numbers. With `gate`, rows are rebuilt in background, which
+    /// waits until the gate opens. With `parallel`, rows are filtered and sorted in parallel.
+    #[derive(Default)]
+    struct Viewer {
+        gate: Option<Arc<AtomicBool>>,
+        #[cfg(feature = "rayon")]
+        parallel: bool,
+    }
+
+    struct Codec;
+
+    impl RowCodec<u32> for Codec {
+        type DeserializeError = ();
+
+        fn create_empty_decoded_row(&mut self) -> u32 {
+            0
+        }
+
+        fn encode_column(&mut self, src_row: &u32, _: usize, dst: &mut String) {
+            dst.push_str(&src_row.to_string());
+        }
+
+        fn decode_column(
+            &mut self,
+            src_data: &str,
+            _: usize,
+            dst_row: &mut u32,
+        ) -> Result<(), DecodeErrorBehavior> {
+            *dst_row = src_data.parse().map_err(|_| DecodeErrorBehavior::SkipCell)?;
+            Ok(())
+        }
+    }
+
+    /// Default matching of [`RowViewer::filter_cell`].
+    fn contains_filter(filter: &str, encoded: Option<&str>) -> bool {
+        encoded.is_none_or(|text| text.to_lowercase().contains(&filter.to_lowercase()))
+    }
+
+    impl RowViewer<u32> for Viewer {
+        fn num_columns(&mut self) -> usize {
+            1
+        }
+
+        fn is_sortable_column(&mut self, _: usize) -> bool {
+            true
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &u32, _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut u32,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &u32, dst: &mut u32, _: usize) {
+            *dst = *src;
+        }
+
+        fn new_empty_row(&mut self) -> u32 {
+            0
+        }
+
+        fn compare_cell(&self, a: &u32, b: &u32, _: usize) -> std::cmp::Ordering {
+            (a / 10).cmp(&(b / 10))
+        }
+
+        fn filter_row(&mut self, row: &u32) -> bool {
+            !row.is_multiple_of(7)
+        }
+
+        fn is_filterable_column(&mut self, _: usize) -> bool {
+            true
+        }
+
+        fn try_create_codec(&mut self, _: bool) -> Option<impl RowCodec<u32>> {
+            Some(Codec)
+        }
+
+        fn is_groupable_column(&mut self, _: usize) -> bool {
+            true
+        }
+
+        fn group_key(&mut self, row: &u32, _: usize, _: Option<&str>) -> String {
+            (row / 10).to_string()
+        }
+
+        #[cfg(feature = "rayon")]
+        fn parallel_row_ops(&self) -> Option<ParallelRowOps<'_, u32>> {
+            let ops = ParallelRowOps::new(|a: &u32, b: &u32, _| (a / 10).cmp(&(b / 10)))
+                .filter_row(|x| !x.is_multiple_of(7))
+                .filter_cell(|_, _, filter, encoded| contains_filter(filter, encoded));
+
+            self.parallel.then_some(ops)
+        }
+
+        fn background_row_ops(&self) -> Option<BackgroundRowOps<u32, impl Send + 'static>> {
+            let gate = self.gate.clone()?;
+
+            let ops = BackgroundRowOps::new(|x: &u32| *x, |a, b, _| (a / 10).cmp(&(b / 10)))
+                .filter_row(move |x| {
+                    while !gate.load(AtomicOrdering::Relaxed) {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+
+                    !x.is_multiple_of(7)
+                })
+                .filter_cell(|_, _, filter, encoded| contains_filter(filter, encoded))
+                .min_rows(0);
+
+            Some(ops)
+        }
+    }
+
+    const EVEN_BG: egui::Color32 = egui::Color32::from_rgb(1, 2, 3);
+    const EVEN_BORDER: egui::Color32 = egui::Color32::from_rgb(4, 5, 6);
+
+    /// Two columns, each of which must stay below 100. Edits of invalid values are rejected.
+    /// The fill handle continues the step between the first two source cells. Even values of the
+    /// first column are styled with `EVEN_BG` and `EVEN_BORDER`. Columns are aggregated by sum.
+    struct PairViewer;
+
+    fn pair_cell(row: &(u32, u32), column: usize) -> u32 {
+        [row.0, row.1][column]
+    }
+
+    impl RowViewer<(u32, u32)> for PairViewer {
+        fn num_columns(&mut self) -> usize {
+            2
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &(u32, u32), _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut (u32, u32),
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &(u32, u32), dst: &mut (u32, u32), column: usize) {
+            match column {
+                0 => dst.0 = src.0,
+                _ => dst.1 = src.1,
+            }
+        }
+
+        fn new_empty_row(&mut self) -> (u32, u32) {
+            (0, 0)
+        }
+
+        fn compare_cell(
+            &self,
+            a: &(u32, u32),
+            b: &(u32, u32),
+            column: usize,
+        ) -> std::cmp::Ordering {
+            match column {
+                0 => a.0.cmp(&b.0),
+                _ => a.1.cmp(&b.1),
+            }
+        }
+
+        fn validate_cell(&mut self, row: &(u32, u32), column: usize) -> CellValidation {
+            match pair_cell(row, column) < 100 {
+                true => CellValidation::Ok,
+                false => CellValidation::Error("too large".into()),
+            }
+        }
+
+        fn reject_invalid_edits(&mut self) -> bool {
+            true
+        }
+
+        fn column_aggregate(&mut self, rows: &[&(u32, u32)], column: usize) -> Option<String> {
+            Some(rows.iter().map(|row| pair_cell(row, column)).sum::<u32>().to_string())
+        }
+
+        fn cell_style(&mut self, row: &(u32, u32), column: usize) -> CellStyle {
+            match column == 0 && row.0.is_multiple_of(2) {
+                true => CellStyle::default().bg(EVEN_BG).border((1., EVEN_BORDER)),
+                false => CellStyle::default(),
+            }
+        }
+
+        fn fill_series(&mut self, fill: &FillSeries<'_, (u32, u32)>) -> Option<(u32, u32)> {
+            let values = fill
+                .sources
+                .iter()
+                .map(|(row, column)| i64::from(pair_cell(row, *column)))
+                .collect_vec();
+            let step = values.get(1).map_or(0, |x| x - values[0]);
+            let value = u32::try_from(values[0] + step * fill.index as i64).ok()?;
+
+            Some(match fill.column {
+                0 => (value, fill.target.1),
+                _ => (fill.target.0, value),
+            })
+        }
+    }
+
+    /// Single text column, which is encoded as is.
+    struct TextViewer;
+
+    impl RowCodec<String> for TextViewer {
+        type DeserializeError = ();
+
+        fn create_empty_decoded_row(&mut self) -> String {
+            String::new()
+        }
+
+        fn encode_column(&mut self, src_row: &String, _: usize, dst: &mut String) {
+            dst.push_str(src_row);
+        }
+
+        fn decode_column(
+            &mut self,
+            src_data: &str,
+            _: usize,
+            dst_row: &mut String,
+        ) -> Result<(), DecodeErrorBehavior> {
+            src_data.clone_into(dst_row);
+            Ok(())
+        }
+    }
+
+    impl RowViewer<String> for TextViewer {
+        fn num_columns(&mut self) -> usize {
+            1
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &String, _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut String,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &String, dst: &mut String, _: usize) {
+            src.clone_into(dst);
+        }
+
+        fn new_empty_row(&mut self) -> String {
+            String::new()
+        }
+
+        fn try_create_codec(&mut self, _: bool) -> Option<impl RowCodec<String>> {
+            Some(TextViewer)
+        }
+    }
+
+    fn validate<R>(table: &mut DataTable<R>, vwr: &mut impl RowViewer<R>) {
+        let mut ui = table.ui.take().unwrap_or_default();
+        ui.validate_identity(vwr);
+        ui.validate_cc(&mut table.rows, vwr);
+        table.ui = Some(ui);
+    }
+
+    fn push_command<R>(
+        table: &mut DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        command: Command<R>,
+    ) {
+        let mut ui = table.ui.take().unwrap();
+        ui.push_new_command(table, vwr, command, 100);
+        table.ui = Some(ui);
+    }
+
+    fn apply_action<R>(table: &mut DataTable<R>, vwr: &mut impl RowViewer<R>, action: UiAction) {
+        let mut ui = table.ui.take().unwrap();
+        for command in ui.try_apply_ui_action(table, vwr, action) {
+            ui.push_new_command(table, vwr, command, 100);
+        }
+        table.ui = Some(ui);
+    }
+
+    /// Row, focused column and value being edited.
+    fn edition<R: Clone>(table: &DataTable<R>) -> Option<(RowIdx, VisColumnPos, R)> {
+        match &table.ui.as_ref()?.cc_cursor {
+            CursorState::Edit {
+                row,
+                last_focus,
+                edition,
+                ..
+            } => Some((*row, *last_focus, edition.clone())),
+            CursorState::Select(_) => None,
+        }
+    }
+
+    fn set_edition<R>(table: &mut DataTable<R>, value: R) {
+        *table.ui.as_mut().unwrap().unwrap_editing_row_data() = value;
+    }
+
+    fn rows(x: &[usize]) -> Vec<RowIdx> {
+        x.iter().copied().map(RowIdx).collect()
+    }
+
+    #[test]
+    fn reject_invalid_edit_of_any_changed_column() {
+        let mut vwr = PairViewer;
+        let mut table = [(1, 2), (300, 4)].into_iter().collect::<DataTable<_>>();
+        validate(&mut table, &mut vwr);
+
+        // The editor of the first column changes the second one as well.
+        let start = |row, col, value: (u32, u32)| {
+            Command::CcEditStart(RowIdx(row), VisColumnPos(col), value.into())
+        };
+        push_command(&mut table, &mut vwr, start(0, 0, (1, 2)));
+        set_edition(&mut table, (10, 200));
+        push_command(&mut table, &mut vwr, Command::CcCommitEdit);
+
+        assert_eq!(edition(&table), Some((RowIdx(0), VisColumnPos(1), (10, 200))));
+        assert_eq!(table.ui.as_ref().unwrap().cc_interactive_cell, VisLinearIdx(1));
+        assert_eq!(table[0], (1, 2));
+
+        set_edition(&mut table, (10, 20));
+        push_command(&mut table, &mut vwr, Command::CcCommitEdit);
+        assert_eq!(edition(&table), None);
+        assert_eq!(table[0], (10, 20));
+
+        // Invalid values left intact don't block the edition of other columns.
+        push_command(&mut table, &mut vwr, start(1, 1, (300, 4)));
+        set_edition(&mut table, (300, 5));
+        push_command(&mut table, &mut vwr, Command::CcCommitEdit);
+        assert_eq!(edition(&table), None);
+        assert_eq!(table[1], (300, 5));
+    }
+
+    #[test]
+    fn reject_invalid_edit_after_commit_and_move() {
+        let mut vwr = PairViewer;
+        let mut table = [(1, 2), (3, 4)].into_iter().collect::<DataTable<_>>();
+        validate(&mut table, &mut vwr);
+
+        // Moving within the row carries the edited row to the next editor.
+        let start = |row, col, value: (u32, u32)| {
+            Command::CcEditStart(RowIdx(row), VisColumnPos(col), value.into())
+        };
+        push_command(&mut table, &mut vwr, start(0, 0, (1, 2)));
+        set_edition(&mut table, (10, 2));
+        let commit_and_move = UiAction::CommitEditionAndMove;
+        apply_action(&mut table, &mut vwr, commit_and_move(MoveDirection::Right));
+        assert_eq!(edition(&table), Some((RowIdx(0), VisColumnPos(1), (10, 2))));
+        assert_eq!(table[0], (10, 2));
+
+        // The invalid value is refused along with the move, which keeps the editor in place.
+        set_edition(&mut table, (500, 20));
+        apply_action(&mut table, &mut vwr, commit_and_move(MoveDirection::Down));
+        assert_eq!(edition(&table), Some((RowIdx(0), VisColumnPos(0), (500, 20))));
+        assert_eq!(table[0], (10, 2));
+
+        // Actions which would change the selection are dropped while the editor is kept open.
+        apply_action(&mut table, &mut vwr, UiAction::MoveSelection(MoveDirection::Down));
+        assert_eq!(edition(&table), Some((RowIdx(0), VisColumnPos(0), (500, 20))));
+
+        set_edition(&mut table, (50, 20));
+        apply_action(&mut table, &mut vwr, commit_and_move(MoveDirection::Down));
+        assert_eq!(edition(&table), Some((RowIdx(1), VisColumnPos(0), (3, 4))));
+        assert_eq!(table[0], (50, 20));
+    }
+
+    #[test]
+    fn row_shifts() {
+        let mut cc_rows = rows(&[3, 0, 2, 1]);
+        RowShift::Insert(RowIdx(2), 2).apply_all(&mut cc_rows);
+        assert_eq!(cc_rows, rows(&[5, 0, 2, 3, 4, 1]));
+
+        RowShift::Remove(rows(&[1, 3])).apply_all(&mut cc_rows);
+        assert_eq!(cc_rows, rows(&[3, 0, 1, 2]));
+
+        RowShift::Map(vec![Some(RowIdx(1)), None, Some(RowIdx(0)), Some(RowIdx(2))])
+            .apply_all(&mut cc_rows);
+        assert_eq!(cc_rows, rows(&[2, 1, 0]));
+
+        // Rows appended at the end follow the row before them.
+        RowShift::Insert(RowIdx(3), 1).apply_all(&mut cc_rows);
+        assert_eq!(cc_rows, rows(&[2, 3, 1, 0]));
+    }
+
+    #[test]
+    fn rebuild_job_shifts() {
+        let mut job = RebuildJob {
+            handle: None,
+            cancel: default(),
+            query: RebuildQuery {
+                sort: default(),
+                filters: default(),
+                viewer_filter_hash: 0,
+            },
+            remap: (0..5).map(|x| Some(RowIdx(x))).collect(),
+            touched: default(),
+            rerun: false,
+        };
+
+        job.shift(&RowShift::Insert(RowIdx(1), 2));
+        assert_eq!(job.remap, [0, 3, 4, 5, 6].map(|x| Some(RowIdx(x))));
+        assert_eq!(job.touched, rows(&[1, 2]).into_iter().collect());
+
+        job.shift(&RowShift::Remove(rows(&[0, 3])));
+        assert_eq!(job.remap, [None, None, Some(RowIdx(2)), Some(RowIdx(3)), Some(RowIdx(4))]);
+        assert_eq!(job.touched, rows(&[0, 1]).into_iter().collect());
+        assert!(!job.rerun);
+
+        job.shift(&RowShift::Map(vec![Some(RowIdx(4)), Some(RowIdx(0))]));
+        assert_eq!(job.touched, rows(&[4, 0]).into_iter().collect());
+        assert!(job.rerun);
+    }
+
+    /// Starts a background rebuild of a sorted table, then modifies rows with `edit` before the
+    /// rebuild is done. The result must be the same as a synchronous rebuild.
+    fn assert_reconciled(edit: impl FnOnce(&mut DataTable<u32>, &mut Viewer)) {
+        let gate = Arc::<AtomicBool>::default();
+        let mut vwr = Viewer::default().tap_mut(|x| x.gate = Some(gate.clone()));
+        let mut table = (0..200).map(|x| x * 37 % 200).collect::<DataTable<_>>();
+
+        validate(&mut table, &mut vwr);
+        push_command(
+            &mut table,
+            &mut vwr,
+            Command::SetColumnSort(vec![(ColumnIdx(0), IsAscending(true))]),
+        );
+        validate(&mut table, &mut vwr);
+        assert!(table.ui.as_ref().unwrap().is_rebuilding());
+
+        edit(&mut table, &mut vwr);
+        validate(&mut table, &mut vwr);
+        assert!(table.ui.as_ref().unwrap().is_rebuilding());
+
+        gate.store(true, AtomicOrdering::Relaxed);
+        while table.ui.as_ref().unwrap().is_rebuilding() {
+            std::thread::sleep(Duration::from_millis(1));
+            validate(&mut table, &mut vwr);
+        }
+
+        let ui = table.ui.as_ref().unwrap();
+        assert!(!ui.p.sort.is_empty());
+
+        let mut sync = Viewer::default();
+        let mut expected = filter_rows(&ui.p, &table.rows, 0..table.rows.len(), &mut sync);
+        sort_rows(&ui.p.sort, &table.rows, &mut expected, &mut sync);
+        assert_eq!(ui.cc_rows, expected);
+    }
+
+    #[test]
+    fn resort_edited_rows() {
+        let mut vwr = Viewer::default();
+        let mut table = (0..200).map(|x| x * 37 % 200).collect::<DataTable<_>>();
+
+        // Unsorted rows stay in place.
+        validate(&mut table, &mut vwr);
+        table.apply(&mut vwr, UserCommand::SetRowValue(0, 150.into()));
+        assert!(table.ui.as_ref().unwrap().cc_edited_rows.is_empty());
+
+        push_command(
+            &mut table,
+            &mut vwr,
+            Command::SetColumnSort(vec![(ColumnIdx(0), IsAscending(true))]),
+        );
+        validate(&mut table, &mut vwr);
+
+        // Moved up, moved down, moved among equal rows, filtered out and filtered in.
+        let hidden = table.iter().position(|x| *x == 14).unwrap();
+        table.edit(&mut vwr, |editor| {
+            editor.set_row(1, 3);
+            editor.set_row(2, 199);
+            editor.set_cell(3, 0, 155);
+            editor.set_row(4, 70);
+            editor.set_cell(hidden, 0, 8);
+        });
+
+        let mut ui = table.ui.take().unwrap();
+        assert_eq!(ui.cc_edited_rows.len(), 5);
+        assert!(ui.resort_edited_rows(&table.rows, &mut vwr));
+
+        let mut expected = filter_rows(&ui.p, &table.rows, 0..table.rows.len(), &mut vwr);
+        sort_rows(&ui.p.sort, &table.rows, &mut expected, &mut vwr);
+        assert_eq!(ui.cc_rows, expected);
+        assert_eq!(ui.cc_row_heights.len(), expected.len());
+        assert!(
+            expected
+                .iter()
+                .enumerate()
+                .all(|(i, row)| ui.cc_row_id_to_vis[row] == VisRowPos(i))
+        );
+    }
+
+    #[test]
+    fn rebuild_with_inserted_rows() {
+        assert_reconciled(|table, vwr| {
+            table.apply(vwr, UserCommand::InsertRows(0, [55, 14, 199].into()));
+            table.apply(vwr, UserCommand::InsertRows(100, [0, 56].into()));
+            table.append([120, 3]);
+        });
+    }
+
+    #[test]
+    fn rebuild_with_removed_rows() {
+        assert_reconciled(|table, vwr| {
+            table.apply(vwr, UserCommand::RemoveRows(vec![0, 5, 6, 150]));
+            table.apply(vwr, UserCommand::InsertRows(3, [42].into()));
+            table.apply(vwr, UserCommand::RemoveRows(vec![3, 196]));
+        });
+    }
+
+    #[test]
+    fn rebuild_with_moved_rows() {
+        assert_reconciled(|table, vwr| {
+            push_command(table, vwr, Command::MoveRows {
+                from: rows(&[0, 1, 50]),
+                to: rows(&[97, 98, 99]),
+            });
+        });
+    }
+
+    #[test]
+    fn rebuild_with_edited_rows() {
+        assert_reconciled(|table, vwr| {
+            // Moved up and down, and filtered out.
+            table.apply(vwr, UserCommand::SetRowValue(0, 199.into()));
+            table.apply(vwr, UserCommand::SetRowValue(10, 1.into()));
+            table.apply(vwr, UserCommand::SetRowValue(20, 70.into()));
+            table.apply(vwr, UserCommand::InsertRows(5, [8].into()));
+            table.apply(vwr, UserCommand::SetRowValue(5, 150.into()));
+        });
+    }
+
+    /// Selects cells from `a` to `b`, then drags the fill handle onto `target`.
+    fn fill(
+        table: &mut DataTable<(u32, u32)>,
+        a: (usize, usize),
+        b: (usize, usize),
+        target: (usize, usize),
+    ) {
+        let mut vwr = PairViewer;
+        validate(table, &mut vwr);
+
+        let linear = |(row, col): (usize, usize)| VisLinearIdx(row * 2 + col);
+        let sel = VisSelection::from_points(2, linear(a), linear(b));
+        push_command(table, &mut vwr, Command::CcSetSelection(vec![sel]));
+
+        let mut ui = table.ui.take().unwrap();
+        ui.begin_fill();
+        ui.update_fill_target(VisRowPos(target.0), VisColumnPos(target.1));
+
+        // Every filled cell is written by a single command, which is undone at once.
+        let commands = ui.finish_fill(table, &mut vwr);
+        assert!(matches!(commands[..], [Command::CcSetCells {
+            context: CellWriteContext::Fill,
+            ..
+        }]));
+
+        for command in commands {
+            ui.push_new_command(table, &mut vwr, command, 100);
+        }
+        table.ui = Some(ui);
+    }
+
+    #[test]
+    fn fill_handle_series() {
+        let original = [(1, 10), (2, 20), (0, 0), (0, 0)];
+        let mut table = original.into_iter().collect::<DataTable<_>>();
+
+        // Down, continuing the step of each column.
+        fill(&mut table, (0, 0), (1, 1), (3, 1));
+        assert_eq!(*table, [(1, 10), (2, 20), (3, 30), (4, 40)]);
+
+        assert!(table.undo(&mut PairViewer));
+        assert_eq!(*table, original);
+        assert!(!table.can_undo());
+
+        // Right, from the single source cell of each row.
+        fill(&mut table, (0, 0), (1, 0), (1, 1));
+        assert_eq!(*table, [(1, 1), (2, 2), (0, 0), (0, 0)]);
+
+        assert!(table.undo(&mut PairViewer));
+        assert_eq!(*table, original);
 
-                    let pos = if self.p.sort.is_empty() {
-                        self.cc_rows[ic_r.0]
-                    } else {
-                        RowIdx(table.rows.len())
-                    };
+        // Up, where the cell adjacent to the filled area is the last source.
+        let mut table = [(0, 0), (0, 0), (5, 0), (7, 0)].into_iter().collect::<DataTable<_>>();
+        fill(&mut table, (2, 0), (3, 0), (0, 0));
+        assert_eq!(*table, [(1, 0), (3, 0), (5, 0), (7, 0)]);
+    }
 
-                    vec![Command::InsertRows(pos, rows)]
-                } else {
-                    vec![]
-                }
-            }
-            UiAction::DeleteSelection => {
-                let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
-                let sels = self.collect_selection();
-                let slab = vec![default].into_boxed_slice();
+    #[test]
+    fn undo_groups() {
+        let mut vwr = PairViewer;
+        let original = [(1, 10), (2, 20)];
+        let mut table = original.into_iter().collect::<DataTable<_>>();
+        validate(&mut table, &mut vwr);
 
-                vec![Command::CcSetCells {
-                    slab,
-                    values: sels
-                        .into_iter()
-                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
-                        .collect(),
-                    context: CellWriteContext::Clear,
-                }]
-            }
-            UiAction::DeleteRow => {
-                if vwr.allow_row_deletions() {
-                    let rows = self
-                        .collect_selected_rows()
-                        .into_iter()
-                        .map(|x| self.cc_rows[x.0])
-                        .filter(|row| vwr.confirm_row_deletion_by_ui(&table.rows[row.0]))
-                        .collect();
+        table.edit(&mut vwr, |editor| {
+            editor.set_cell(0, 0, (5, 0)).set_row(1, (6, 60)).insert_rows(2, [(7, 70)]);
+            editor.new_undo_group().remove_rows([0]);
 
-                    vec![Command::RemoveRow(rows)]
-                } else {
-                    vec![]
-                }
-            }
-            UiAction::SelectAll => {
-                if self.cc_rows.is_empty() {
-                    return vec![];
-                }
+            // Empty groups leave no entry.
+            editor.new_undo_group().new_undo_group();
+        });
 
-                vec![Command::CcSetSelection(vec![VisSelection(
-                    VisLinearIdx(0),
-                    VisRowPos(self.cc_rows.len().saturating_sub(1)).linear_index(
-                        self.p.vis_cols.len(),
-                        VisColumnPos(self.p.vis_cols.len() - 1),
-                    ),
-                )])]
-            }
+        let first = [(5, 10), (6, 60), (7, 70)];
+        let second = [(6, 60), (7, 70)];
+        assert_eq!(*table, second);
+        assert_eq!(table.history().len(), 2);
 
-            action @ (UiAction::NavPageDown
-            | UiAction::NavPageUp
-            | UiAction::NavTop
-            | UiAction::NavBottom) => {
-                let ofst = match action {
-                    UiAction::NavPageDown => self.cci_page_row_count as isize,
-                    UiAction::NavPageUp => -(self.cci_page_row_count as isize),
-                    UiAction::NavTop => isize::MIN,
-                    UiAction::NavBottom => isize::MAX,
-                    _ => unreachable!(),
-                };
+        // Every change of a group is undone and redone in a single step.
+        assert!(table.undo(&mut vwr));
+        assert_eq!(*table, first);
+        assert!(table.undo(&mut vwr));
+        assert_eq!(*table, original);
+        assert!(!table.can_undo());
 
-                let new_ic_r = (ic_r.0 as isize)
-                    .saturating_add(ofst)
-                    .clamp(0, self.cc_rows.len().saturating_sub(1) as _);
-                self.cc_interactive_cell =
-                    VisLinearIdx(new_ic_r as usize * self.p.vis_cols.len() + ic_c.0);
+        assert!(table.redo(&mut vwr));
+        assert_eq!(*table, first);
 
-                self.validate_interactive_cell(self.p.vis_cols.len());
-                vec![Command::CcSetSelection(vec![VisSelection(
-                    self.cc_interactive_cell,
-                    self.cc_interactive_cell,
-                )])]
-            }
-        }
+        // An empty edit records nothing, thus the redo is kept.
+        table.edit(&mut vwr, |editor| {
+            editor.new_undo_group();
+        });
+        assert_eq!(table.history().len(), 2);
+        assert!(table.redo(&mut vwr));
+        assert_eq!(*table, second);
     }
 
-    fn collect_selection(&self) -> BTreeSet<(VisRowPos, VisColumnPos)> {
-        let mut set = BTreeSet::new();
+    /// Renders a frame of the table in a small window.
+    fn render<R>(
+        ctx: &egui::Context,
+        table: &mut DataTable<R>,
+        vwr: &mut impl RowViewer<R>,
+        style: crate::Style,
+    ) -> egui::FullOutput {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(default(), egui::vec2(400., 300.))),
+            ..default()
+        };
 
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            for sel in selections.iter() {
-                let (top, left) = sel.0.row_col(self.p.vis_cols.len());
-                let (bottom, right) = sel.1.row_col(self.p.vis_cols.len());
+        ctx.run_ui(input, |ui| {
+            ui.add(crate::Renderer::new(table, vwr).with_style(style));
+        })
+    }
 
-                for r in top.0..=bottom.0 {
-                    for c in left.0..=right.0 {
-                        set.insert((VisRowPos(r), VisColumnPos(c)));
-                    }
-                }
+    /// Fill and stroke colors of rectangles, and fill colors of paths painted in the frame.
+    fn painted_colors(output: &egui::FullOutput) -> Vec<egui::Color32> {
+        fn collect(shape: &egui::Shape, colors: &mut Vec<egui::Color32>) {
+            match shape {
+                egui::Shape::Vec(shapes) => shapes.iter().for_each(|x| collect(x, colors)),
+                egui::Shape::Rect(rect) => colors.extend([rect.fill, rect.stroke.color]),
+                egui::Shape::Path(path) => colors.push(path.fill),
+                _ => {}
             }
         }
 
-        set
+        let mut colors = Vec::new();
+        for clipped in &output.shapes {
+            collect(&clipped.shape, &mut colors);
+        }
+        colors
     }
 
-    fn collect_selected_rows(&self) -> BTreeSet<VisRowPos> {
-        let mut rows = BTreeSet::new();
+    #[test]
+    fn appended_rows_are_filtered_and_sorted() {
+        let mut vwr = Viewer::default();
+        let mut table = (0..100).rev().collect::<DataTable<u32>>();
+        validate(&mut table, &mut vwr);
+        push_command(
+            &mut table,
+            &mut vwr,
+            Command::SetColumnSort(vec![(ColumnIdx(0), IsAscending(true))]),
+        );
+        validate(&mut table, &mut vwr);
 
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            for sel in selections.iter() {
-                let (top, _) = sel.0.row_col(self.p.vis_cols.len());
-                let (bottom, _) = sel.1.row_col(self.p.vis_cols.len());
+        // An edit before the append, which is undone onto the same row afterwards.
+        table.apply(&mut vwr, UserCommand::SetRowValue(10, 150.into()));
+        validate(&mut table, &mut vwr);
 
-                for r in top.0..=bottom.0 {
-                    rows.insert(VisRowPos(r));
-                }
+        table.append([35, 14, 200, 0, 36]);
+        table.extend([1]);
+        assert!(!table.ui.as_ref().unwrap().cc_dirty);
+        validate(&mut table, &mut vwr);
+
+        // Same as a full rebuild.
+        let ui = table.ui.as_ref().unwrap();
+        let mut expected = filter_rows(&ui.p, &table.rows, 0..table.rows.len(), &mut vwr);
+        sort_rows(&ui.p.sort, &table.rows, &mut expected, &mut vwr);
+        assert_eq!(ui.cc_rows, expected);
+
+        assert!(table.undo(&mut vwr));
+        assert_eq!(table[10], 89);
+        assert_eq!(table[100..], [35, 14, 200, 0, 36, 1]);
+
+        assert!(table.redo(&mut vwr));
+        assert_eq!(table[10], 150);
+    }
+
+    #[test]
+    fn follow_tail_of_appended_rows() {
+        // Rows have a fixed height, as cells of the viewer are empty.
+        let last_rendered_row = |follow_tail| {
+            let ctx = egui::Context::default();
+            let mut vwr = Viewer::default();
+            let mut table = (100..200).collect::<DataTable<u32>>();
+            let style = crate::Style {
+                follow_tail,
+                table_row_height: Some(20.),
+                ..default()
+            };
+
+            for _ in 0..3 {
+                render(&ctx, &mut table, &mut vwr, style);
             }
-        }
 
-        rows
+            table.append(200..=400);
+
+            for _ in 0..3 {
+                render(&ctx, &mut table, &mut vwr, style);
+            }
+
+            table.ui.as_ref().unwrap().rendered_rows().end
+        };
+
+        assert_eq!(last_rendered_row(true), 301);
+        assert!(last_rendered_row(false) < 50);
     }
 
-    fn moved_position(&self, pos: VisLinearIdx, dir: MoveDirection) -> VisLinearIdx {
-        let (VisRowPos(r), VisColumnPos(c)) = pos.row_col(self.p.vis_cols.len());
+    #[test]
+    fn replace_all_matches() {
+        let replaced = |match_case| {
+            let mut vwr = TextViewer;
+            let original = ["Apple pie", "apple", "banana", "APPLE"].map(String::from);
+            let mut table = original.iter().cloned().collect::<DataTable<String>>();
+            validate(&mut table, &mut vwr);
+
+            let mut ui = table.ui.take().unwrap();
+            ui.find.query = "apple".into();
+            ui.find.replacement = "pear".into();
+            ui.find.match_case = match_case;
+            let commands = ui.find_replace(&table, &mut vwr, true);
+            table.ui = Some(ui);
+
+            // Every replaced cell is written by a single command.
+            let [Command::CcSetCells { values, context, .. }] = &commands[..] else {
+                panic!("expected a single SetCells command");
+            };
+            assert_eq!(*context, CellWriteContext::Replace);
+            let num_replaced = values.len();
 
-        let (rmax, cmax) = (
-            self.cc_rows.len().saturating_sub(1),
-            self.p.vis_cols.len().saturating_sub(1),
-        );
+            for command in commands {
+                push_command(&mut table, &mut vwr, command);
+            }
 
-        let (nr, nc) = match dir {
-            MoveDirection::Up => match (r, c) {
-                (0, c) => (0, c),
-                (r, c) => (r - 1, c),
-            },
-            MoveDirection::Down => match (r, c) {
-                (r, c) if r == rmax => (r, c),
-                (r, c) => (r + 1, c),
-            },
-            MoveDirection::Left => match (r, c) {
-                (0, 0) => (0, 0),
-                (r, 0) => (r - 1, cmax),
-                (r, c) => (r, c - 1),
-            },
-            MoveDirection::Right => match (r, c) {
-                (r, c) if r == rmax && c == cmax => (r, c),
-                (r, c) if c == cmax => (r + 1, 0),
-                (r, c) => (r, c + 1),
-            },
+            let result = table.to_vec();
+            assert_eq!(result.iter().zip(&original).filter(|(a, b)| a != b).count(), num_replaced);
+
+            assert!(table.undo(&mut vwr));
+            assert_eq!(table[..], original);
+            result
         };
 
-        VisLinearIdx(nr * self.p.vis_cols.len() + nc)
+        assert_eq!(replaced(false), ["pear pie", "pear", "banana", "pear"]);
+        assert_eq!(replaced(true), ["Apple pie", "pear", "banana", "APPLE"]);
     }
 
-    pub fn cci_take_selection(&mut self, mods: egui::Modifiers) -> Option<Vec<VisSelection>> {
-        let ncol = self.p.vis_cols.len();
-        let cci_sel = self
-            .cci_selection
-            .take()
-            .map(|(_0, _1)| VisSelection::from_points(ncol, _0, _1))?;
+    #[test]
+    fn group_rows() {
+        // Group headers are shown as their key in brackets, rows as their values.
+        let display = |table: &DataTable<u32>| {
+            let ui = table.ui.as_ref().unwrap();
+            let display = (0..ui.num_display_rows())
+                .map(|index| match ui.display_row(index) {
+                    DisplayRow::Group(g) => format!("[{}]", ui.group(g).key),
+                    DisplayRow::Row(vis_row) => {
+                        assert_eq!(ui.display_index(vis_row), index);
+                        table[ui.cc_rows[vis_row.0].0].to_string()
+                    }
+                })
+                .collect_vec();
 
-        if mods.is_none() {
-            return Some(vec![cci_sel]);
-        }
+            (display, ui.cc_row_id_to_vis.len())
+        };
 
-        let mut sel = self.cursor_as_selection().unwrap_or_default().to_owned();
-        let idx_contains = sel.iter().position(|x| x.contains_rect(ncol, cci_sel));
-        if sel.is_empty() {
-            sel.push(cci_sel);
-            return Some(sel);
-        }
+        // Rows are grouped by tens; 21 and 14 are hidden.
+        let mut vwr = Viewer::default();
+        let mut table = [25, 3, 21, 14, 8, 29, 12, 17].into_iter().collect::<DataTable<u32>>();
+        validate(&mut table, &mut vwr);
+        push_command(
+            &mut table,
+            &mut vwr,
+            Command::SetColumnSort(vec![(ColumnIdx(0), IsAscending(false))]),
+        );
+        table.ui.as_mut().unwrap().set_group_by(Some(ColumnIdx(0)));
+        validate(&mut table, &mut vwr);
+
+        // Groups follow the sort order.
+        let (shown, num_vis_rows) = display(&table);
+        assert_eq!(shown, ["[2]", "25", "29", "[1]", "12", "17", "[0]", "3", "8"]);
+        assert_eq!(num_vis_rows, 6);
+
+        table.ui.as_mut().unwrap().toggle_group_collapsed(1);
+        validate(&mut table, &mut vwr);
+
+        let (shown, num_vis_rows) = display(&table);
+        assert_eq!(shown, ["[2]", "25", "29", "[1]", "[0]", "3", "8"]);
+        assert_eq!(num_vis_rows, 4);
+        let ui = table.ui.as_ref().unwrap();
+        assert!(ui.group(1).is_collapsed);
+        assert_eq!(ui.group(1).rows, [RowIdx(6), RowIdx(7)]);
+
+        table.ui.as_mut().unwrap().toggle_group_collapsed(1);
+        validate(&mut table, &mut vwr);
+        assert_eq!(display(&table).0.len(), 9);
+    }
 
-        if mods.command_only() {
-            if let Some(idx) = idx_contains {
-                sel.remove(idx);
-            } else {
-                sel.push(cci_sel);
-            }
-        }
+    #[test]
+    fn frozen_panes() {
+        let mut vwr = PairViewer;
+        let mut table = (0..100).map(|x| (x, 0)).collect::<DataTable<(u32, u32)>>();
+        validate(&mut table, &mut vwr);
+
+        // At least a column is left scrolling. Freezing doesn't rebuild rows.
+        let ui = table.ui.as_mut().unwrap();
+        ui.set_frozen_columns(5);
+        ui.set_frozen_rows(500);
+        assert_eq!((ui.frozen_columns(), ui.frozen_rows()), (1, 100));
+        assert!(!ui.cc_dirty);
+
+        ui.set_frozen_rows(2);
+
+        let ctx = egui::Context::default();
+        let style = crate::Style {
+            follow_tail: true,
+            table_row_height: Some(20.),
+            ..default()
+        };
 
-        if mods.cmd_ctrl_matches(Modifiers::SHIFT) {
-            let last = sel.last_mut().unwrap();
-            if cci_sel.is_point() && last.is_point() {
-                *last = last.union(ncol, cci_sel);
-            } else if idx_contains.is_none() {
-                sel.push(cci_sel);
-            };
+        for _ in 0..3 {
+            render(&ctx, &mut table, &mut vwr, style);
         }
 
-        Some(sel)
+        // Frozen rows stay on top of the scrolled rows, and frozen columns follow the scroll.
+        let ui = table.ui.as_ref().unwrap();
+        assert_eq!(ui.rendered_rows(), 0..100);
+        assert!(ui.cci_scroll_offset > 1000.);
     }
-}
 
-/* ------------------------------------------ Commands ------------------------------------------ */
+    #[test]
+    fn cell_style_is_painted() {
+        let ctx = egui::Context::default();
+        let mut vwr = PairViewer;
+        let mut table = [(1, 2), (2, 3), (4, 5)].into_iter().collect::<DataTable<(u32, u32)>>();
 
-/// NOTE: `Cc` prefix stands for cache command which won't be stored in undo/redo queue, since they
-/// are not called from `cmd_apply` method.
-pub(crate) enum Command<R> {
-    CcHideColumn(ColumnIdx),
-    CcShowColumn {
-        what: ColumnIdx,
-        at: VisColumnPos,
-    },
-    CcReorderColumn {
-        from: VisColumnPos,
-        to: VisColumnPos,
-    },
+        // The first frame only measures the table.
+        render(&ctx, &mut table, &mut vwr, default());
+        let colors = painted_colors(&render(&ctx, &mut table, &mut vwr, default()));
 
-    SetColumnSort(Vec<(ColumnIdx, IsAscending)>),
-    SetVisibleColumns(Vec<ColumnIdx>),
+        let count = |color| colors.iter().filter(|x| **x == color).count();
+        assert_eq!(count(EVEN_BG), 2);
+        assert_eq!(count(EVEN_BORDER), 2);
+    }
 
-    CcSetSelection(Vec<VisSelection>), // Cache - Set Selection
+    #[test]
+    fn invalid_cells_are_marked() {
+        let ctx = egui::Context::default();
+        let mut vwr = PairViewer;
+        let mut table = [(1, 2), (150, 3), (5, 200)].into_iter().collect::<DataTable<(u32, u32)>>();
 
-    SetRowValue(RowIdx, Box<R>),
-    CcSetCells {
-        slab: Box<[R]>,
-        values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]>,
-        context: CellWriteContext,
-    },
-    SetCells {
-        slab: Box<[R]>,
-        values: Box<[(RowIdx, ColumnIdx, RowSlabIndex)]>,
-    },
+        render(&ctx, &mut table, &mut vwr, default());
+        let colors = painted_colors(&render(&ctx, &mut table, &mut vwr, default()));
 
-    InsertRows(RowIdx, Box<[R]>),
-    RemoveRow(Vec<RowIdx>),
+        let error_color = ctx.global_style().visuals.error_fg_color;
+        assert_eq!(colors.iter().filter(|x| **x == error_color).count(), 2);
+    }
 
-    CcEditStart(RowIdx, VisColumnPos, Box<R>),
-    CcCancelEdit,
-    CcCommitEdit,
+    #[test]
+    fn footer_aggregates() {
+        let footer = |table: &mut DataTable<(u32, u32)>, vwr: &mut PairViewer| {
+            let mut ui = table.ui.take().unwrap();
+            ui.validate_footer(table, vwr);
+            let footer = ui.footer().unwrap();
+            let result = (
+                footer.aggregates.clone(),
+                footer.is_selection,
+                footer.num_selected_cells,
+                footer.num_selected_rows,
+            );
+            table.ui = Some(ui);
+            result
+        };
+        let sums = |a: u32, b: u32| vec![Some(a.to_string()), Some(b.to_string())];
 
-    CcUpdateSystemClipboard(String),
+        let mut vwr = PairViewer;
+        let mut table = [(1, 10), (2, 20), (3, 30)].into_iter().collect::<DataTable<_>>();
+        validate(&mut table, &mut vwr);
+
+        // Every visible row, unless more than a cell is selected.
+        assert_eq!(footer(&mut table, &mut vwr), (sums(6, 60), false, 0, 0));
+
+        let sel = VisSelection(VisLinearIdx(0), VisLinearIdx(3));
+        push_command(&mut table, &mut vwr, Command::CcSetSelection(vec![sel]));
+        assert_eq!(footer(&mut table, &mut vwr), (sums(3, 30), true, 4, 2));
+
+        // Edits are reflected.
+        table.apply(&mut vwr, UserCommand::SetRowValue(0, (5, 10).into()));
+        validate(&mut table, &mut vwr);
+        assert_eq!(footer(&mut table, &mut vwr), (sums(7, 30), true, 4, 2));
+    }
+
+    #[test]
+    fn move_selected_rows() {
+        // Selects visible rows, then drops them before or after the target row. 7 is hidden.
+        let moved = |selected: &[(usize, usize)], target, after| {
+            let mut vwr = Viewer::default();
+            let mut table = (1..=8).collect::<DataTable<u32>>();
+            validate(&mut table, &mut vwr);
+
+            let sels = selected
+                .iter()
+                .map(|(top, bottom)| VisSelection(VisLinearIdx(*top), VisLinearIdx(*bottom)))
+                .collect();
+            push_command(&mut table, &mut vwr, Command::CcSetSelection(sels));
+
+            let ui = table.ui.as_mut().unwrap();
+            for command in ui.move_selected_rows(VisRowPos(target), after) {
+                push_command(&mut table, &mut vwr, command);
+            }
+
+            validate(&mut table, &mut vwr);
+            let result = table.to_vec();
+
+            // A single undo restores the original order.
+            let original = (1..=8).collect_vec();
+            assert_eq!(table.undo(&mut vwr), result != original);
+            assert_eq!(*table, original);
+
+            result
+        };
+
+        // Down, dropped after or before the target.
+        assert_eq!(moved(&[(0, 1)], 3, true), [3, 4, 1, 2, 5, 6, 7, 8]);
+        assert_eq!(moved(&[(0, 1)], 3, false), [3, 1, 2, 4, 5, 6, 7, 8]);
+        assert_eq!(moved(&[(0, 0)], 6, true), [2, 3, 4, 5, 6, 7, 8, 1]);
+
+        // Up.
+        assert_eq!(moved(&[(4, 5)], 1, false), [1, 5, 6, 2, 3, 4, 7, 8]);
+        assert_eq!(moved(&[(4, 5)], 1, true), [1, 2, 5, 6, 3, 4, 7, 8]);
+        assert_eq!(moved(&[(6, 6)], 0, false), [8, 1, 2, 3, 4, 5, 6, 7]);
+
+        // Rows on both sides of the target are gathered at it.
+        assert_eq!(moved(&[(0, 0), (4, 4)], 2, false), [2, 1, 5, 3, 4, 6, 7, 8]);
+        assert_eq!(moved(&[(0, 0), (4, 4)], 2, true), [2, 3, 1, 5, 4, 6, 7, 8]);
+
+        // Dropping rows onto themselves changes nothing.
+        assert_eq!(moved(&[(1, 3)], 2, false), (1..=8).collect_vec());
+        assert_eq!(moved(&[(1, 3)], 3, true), (1..=8).collect_vec());
+    }
+
+    /// Rows filtered on the UI thread, in parallel and in background are the same, as every
+    /// filter matches the same encoded cells.
+    #[test]
+    fn filters_match_encoded_cells() {
+        let rows = || (0..500).map(|x| x * 37 % 500);
+        let filtered = |mut vwr: Viewer| {
+            let mut table = rows().collect::<DataTable<_>>();
+            validate(&mut table, &mut vwr);
+            table.ui.as_mut().unwrap().set_column_filter(ColumnIdx(0), "3".into());
+            validate(&mut table, &mut vwr);
+
+            while table.ui.as_ref().unwrap().is_rebuilding() {
+                std::thread::sleep(Duration::from_millis(1));
+                validate(&mut table, &mut vwr);
+            }
+
+            let ui = table.ui.as_ref().unwrap();
+            ui.cc_rows.iter().map(|x| table.rows[x.0]).collect_vec()
+        };
+
+        let serial = filtered(Viewer::default());
+        assert_eq!(
+            serial,
+            rows()
+                .filter(|x| !x.is_multiple_of(7) && x.to_string().contains('3'))
+                .collect_vec()
+        );
+
+        let gate = Arc::new(AtomicBool::new(true));
+        let background = filtered(Viewer::default().tap_mut(|x| x.gate = Some(gate)));
+        assert_eq!(background, serial);
+
+        #[cfg(feature = "rayon")]
+        assert_eq!(filtered(Viewer { parallel: true, ..default() }), serial);
+    }
 }
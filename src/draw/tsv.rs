@@ -18,14 +18,21 @@ pub fn write_content(buf: &mut String, mut item: &str) {
 
     buf.reserve(item.len());
 
+    // A double quote at the start of the item, or after a comma, would make the dump look like
+    // quoted TSV or CSV to `csv::ParsedCsv::parse_detected`; thus it's escaped.
+    let mut prev = None;
+
     for char in item.chars() {
         match char {
             '\t' => buf.push_str(r"\t"),
             '\n' => buf.push_str(r"\n"),
             '\r' => buf.push_str(r"\r"),
             '\\' => buf.push_str(r"\\"),
+            '"' if matches!(prev, None | Some(',')) => buf.push_str(r#"\""#),
             _ => buf.push(char),
         }
+
+        prev = Some(char);
     }
 }
 
@@ -93,6 +100,7 @@ impl ParsedTsv {
                         'n' => s.data.push('\n'),
                         'r' => s.data.push('\r'),
                         '\\' => s.data.push('\\'),
+                        '"' => s.data.push('"'),
                         ch => {
                             // Just add the character as it is.
                             s.data.push('\\');
@@ -0,0 +1,411 @@
+//! Programmatic access to the undo history of [`DataTable`].
+
+use std::hash::{Hash, Hasher};
+
+use crate::{
+    draw::{
+        state::{Command, UndoArg},
+        EnglishTranslator, Translator,
+    },
+    viewer::{CustomActionEditor, RowCodec},
+    DataTable, RowViewer, UserCommand,
+};
+
+/// Kind of change recorded in an undo history entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HistoryEntryKind {
+    SetCells,
+    SetRow,
+    InsertRows,
+    RemoveRows,
+    MoveRows,
+    SetColumnSort,
+    SetVisibleColumns,
+}
+
+impl HistoryEntryKind {
+    /// Key to look up with [`Translator`] for the label.
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            Self::SetCells => "history-set-cells",
+            Self::SetRow => "history-set-row",
+            Self::InsertRows => "history-insert-rows",
+            Self::RemoveRows => "history-remove-rows",
+            Self::MoveRows => "history-move-rows",
+            Self::SetColumnSort => "history-set-column-sort",
+            Self::SetVisibleColumns => "history-set-visible-columns",
+        }
+    }
+}
+
+/// An entry of the undo history. See [`DataTable::history`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub kind: HistoryEntryKind,
+
+    /// Number of affected cells or rows, depending on the kind.
+    pub count: usize,
+
+    /// Undone entries are the ones which can be redone.
+    pub is_undone: bool,
+}
+
+impl HistoryEntry {
+    /// Human-readable label, e.g. "Insert rows (3)".
+    pub fn label(&self, translator: &dyn Translator) -> String {
+        let text = translator.translate(self.kind.translation_key());
+
+        if self.count > 1 {
+            format!("{text} ({})", self.count)
+        } else {
+            text
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label(&EnglishTranslator::default()))
+    }
+}
+
+/// Snapshot of undo history of a [`DataTable`], which can be serialized to restore the history
+/// over sessions. See [`DataTable::export_undo_history`].
+///
+/// As undo history refers rows by index, it is only valid for the same row data it was taken
+/// from. It's checked by the number of rows, and by a fingerprint of the rows, which is taken
+/// from [`RowViewer::row_key`] of every row, or from rows encoded by the codec otherwise.
+#[derive(Clone)]
+#[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
+pub struct UndoHistory<R> {
+    pub(crate) entries: Vec<UndoArg<R>>,
+    pub(crate) cursor: usize,
+
+    /// Row data at the moment; used to reject mismatching data.
+    pub(crate) rows: RowsFingerprint,
+}
+
+/// Identifies row data which undo history applies to. See [`UndoHistory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct RowsFingerprint {
+    num_rows: usize,
+
+    /// `None` if the viewer provides neither row keys nor a codec.
+    hash: Option<u64>,
+}
+
+impl RowsFingerprint {
+    pub(crate) fn new<R>(rows: &[R], vwr: &mut impl RowViewer<R>) -> Self {
+        let mut hasher = Fnv1a::default();
+
+        let keys = rows.iter().map(|row| vwr.row_key(row)).collect::<Option<Vec<_>>>();
+        let hash = match keys {
+            Some(keys) if !keys.is_empty() => {
+                keys.hash(&mut hasher);
+                Some(hasher.finish())
+            }
+            _ => {
+                let num_columns = vwr.num_columns();
+                vwr.try_create_codec(true).map(|mut codec| {
+                    let mut buf = String::new();
+
+                    for row in rows {
+                        for column in 0..num_columns {
+                            buf.clear();
+                            codec.encode_column(row, column, &mut buf);
+                            buf.hash(&mut hasher);
+                        }
+                    }
+
+                    hasher.finish()
+                })
+            }
+        };
+
+        Self {
+            num_rows: rows.len(),
+            hash,
+        }
+    }
+}
+
+/// FNV-1a, whose hashes stay the same across processes and builds, unlike the ones of hashers
+/// with random keys; fingerprints are compared with the ones restored from storage.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl<R> UndoHistory<R> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<R> DataTable<R> {
+    /// Applies the command just like the ones returned from custom actions; i.e. it's recorded
+    /// as an undo history entry, and `on_row_*` callbacks of the viewer are invoked. Unlike modifying rows
+    /// through `DerefMut`, the UI state (selection, undo history) is preserved. Any ongoing cell
+    /// edition is committed first, or discarded if [`RowViewer::reject_invalid_edits`] refuses
+    /// to commit it.
+    ///
+    /// Row indices refer to the storage order, i.e. the index into `Vec<R>`.
+    ///
+    /// # Panics
+    ///
+    /// If any of row indices is out of range.
+    pub fn apply<V: RowViewer<R>>(&mut self, viewer: &mut V, command: UserCommand<R>) {
+        self.apply_all(viewer, vec![vec![command]]);
+    }
+
+    /// Queues multiple changes through [`CustomActionEditor`], then applies them in order. They're
+    /// undone at once, unless split by [`CustomActionEditor::new_undo_group`]. See
+    /// [`DataTable::apply`].
+    pub fn edit<V: RowViewer<R>>(
+        &mut self,
+        viewer: &mut V,
+        f: impl FnOnce(&mut CustomActionEditor<R>),
+    ) {
+        let mut editor = CustomActionEditor::new();
+        f(&mut editor);
+        self.apply_all(viewer, editor.into_command_groups());
+    }
+
+    fn apply_all<V: RowViewer<R>>(&mut self, viewer: &mut V, groups: Vec<Vec<UserCommand<R>>>) {
+        let mut ui = self.ui.take().unwrap_or_default();
+        ui.validate_identity(viewer);
+        ui.validate_row_keys(&self.rows, viewer);
+
+        // Programmatic changes can't wait for the user to fix an edition refused to be
+        // committed; discard it instead.
+        let capacity = ui.undo_capacity;
+        ui.push_new_command(self, viewer, Command::CcCommitEdit, capacity);
+        ui.cancel_edition();
+
+        ui.apply_user_commands(self, viewer, groups);
+        self.ui = Some(ui);
+    }
+
+    /// Undo the latest change, just like the undo hotkey does. Any ongoing cell edition is
+    /// discarded. Returns false if there's nothing to undo.
+    pub fn undo<V: RowViewer<R>>(&mut self, viewer: &mut V) -> bool {
+        let Some(mut ui) = self.ui.take() else {
+            return false;
+        };
+
+        ui.validate_row_keys(&self.rows, viewer);
+        ui.cancel_edition();
+        let undone = ui.undo(self, viewer);
+
+        self.ui = Some(ui);
+        undone
+    }
+
+    /// Redo the latest undone change, just like the redo hotkey does. Any ongoing cell edition
+    /// is discarded. Returns false if there's nothing to redo.
+    pub fn redo<V: RowViewer<R>>(&mut self, viewer: &mut V) -> bool {
+        let Some(mut ui) = self.ui.take() else {
+            return false;
+        };
+
+        ui.validate_row_keys(&self.rows, viewer);
+        ui.cancel_edition();
+        let redone = ui.redo(self, viewer);
+
+        self.ui = Some(ui);
+        redone
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.ui.as_ref().is_some_and(|ui| ui.has_undo())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.ui.as_ref().is_some_and(|ui| ui.has_redo())
+    }
+
+    /// Discards every undo / redo history.
+    pub fn clear_history(&mut self) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.clear_undo_history();
+        }
+    }
+
+    /// Takes a snapshot of undo history. Store it anywhere, e.g. along with the row data, then
+    /// give it back to [`DataTable::restore_undo_history`] when the rows are restored. To keep it
+    /// in egui memory instead, see [`Renderer::with_undo_history_persistency`].
+    ///
+    /// [`Renderer::with_undo_history_persistency`]: crate::Renderer::with_undo_history_persistency
+    pub fn export_undo_history<V: RowViewer<R>>(&self, viewer: &mut V) -> UndoHistory<R>
+    where
+        R: Clone,
+    {
+        let rows = RowsFingerprint::new(&self.rows, viewer);
+
+        match self.ui.as_ref() {
+            Some(ui) => ui.export_undo_history(rows),
+            None => UndoHistory {
+                entries: Vec::new(),
+                cursor: 0,
+                rows,
+            },
+        }
+    }
+
+    /// Replaces undo history with the snapshot. It must be taken from the identical row data;
+    /// returns false and discards the snapshot if the number of rows or the fingerprint of rows
+    /// differs. See [`UndoHistory`].
+    pub fn restore_undo_history<V: RowViewer<R>>(
+        &mut self,
+        viewer: &mut V,
+        history: UndoHistory<R>,
+    ) -> bool {
+        let rows = RowsFingerprint::new(&self.rows, viewer);
+        self.ui
+            .get_or_insert_with(Default::default)
+            .restore_undo_history(history, rows)
+    }
+
+    /// Lists undo history, from the most recent entry. Leading entries marked as
+    /// [`HistoryEntry::is_undone`] are the ones which can be redone.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.ui
+            .as_ref()
+            .map(|ui| ui.history_entries())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(all(test, feature = "persistency"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct Viewer;
+
+    impl RowViewer<u32> for Viewer {
+        fn num_columns(&mut self) -> usize {
+            1
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &u32, _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut u32,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &u32, dst: &mut u32, _: usize) {
+            *dst = *src;
+        }
+
+        fn new_empty_row(&mut self) -> u32 {
+            0
+        }
+
+        fn row_key(&mut self, row: &u32) -> Option<u64> {
+            Some(u64::from(*row))
+        }
+    }
+
+    #[derive(Default)]
+    struct Storage(HashMap<String, String>);
+
+    impl eframe::Storage for Storage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn remove_string(&mut self, key: &str) {
+            self.0.remove(key);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn serialized_history_round_trip() {
+        let mut table: DataTable<u32> = [1, 2, 3].into_iter().collect();
+        table.apply(&mut Viewer, UserCommand::SetRowValue(1, Box::new(20)));
+        table.apply(&mut Viewer, UserCommand::InsertRows(3, Box::new([4, 5])));
+        table.apply(&mut Viewer, UserCommand::RemoveRows(vec![0]));
+        assert_eq!(*table, [20, 3, 4, 5]);
+
+        let mut storage = Storage::default();
+        eframe::set_value(&mut storage, "history", &table.export_undo_history(&mut Viewer));
+
+        // Restored into a new table with the same rows, as after an app restart.
+        let history: UndoHistory<u32> = eframe::get_value(&storage, "history").unwrap();
+        assert_eq!(history.len(), 3);
+
+        let mut restored: DataTable<u32> = table.iter().copied().collect();
+        assert!(restored.restore_undo_history(&mut Viewer, history));
+
+        assert!(restored.undo(&mut Viewer));
+        assert_eq!(*restored, [1, 20, 3, 4, 5]);
+        assert!(restored.undo(&mut Viewer));
+        assert_eq!(*restored, [1, 20, 3]);
+        assert!(restored.undo(&mut Viewer));
+        assert_eq!(*restored, [1, 2, 3]);
+        assert!(!restored.undo(&mut Viewer));
+
+        assert!(restored.redo(&mut Viewer));
+        assert_eq!(*restored, [1, 20, 3]);
+
+        // Snapshots of different rows are rejected.
+        let history = eframe::get_value(&storage, "history").unwrap();
+        let mut other: DataTable<u32> = [1, 2].into_iter().collect();
+        assert!(!other.restore_undo_history(&mut Viewer, history));
+        assert!(!other.can_undo());
+    }
+
+    #[test]
+    fn history_of_other_rows_is_rejected() {
+        let mut table: DataTable<u32> = [1, 2, 3].into_iter().collect();
+        table.apply(&mut Viewer, UserCommand::SetRowValue(1, Box::new(20)));
+
+        let mut storage = Storage::default();
+        eframe::set_value(&mut storage, "history", &table.export_undo_history(&mut Viewer));
+
+        // Same number of rows, but different ones.
+        let history = eframe::get_value(&storage, "history").unwrap();
+        let mut other: DataTable<u32> = [1, 2, 3].into_iter().collect();
+        assert!(!other.restore_undo_history(&mut Viewer, history));
+        assert!(!other.can_undo());
+
+        let history = eframe::get_value(&storage, "history").unwrap();
+        let mut restored: DataTable<u32> = [1, 20, 3].into_iter().collect();
+        assert!(restored.restore_undo_history(&mut Viewer, history));
+        assert!(restored.can_undo());
+    }
+}
@@ -0,0 +1,524 @@
+//! Bulk import / export of the whole table as text, through [`RowCodec`].
+//!
+//! [`RowCodec`]: crate::viewer::RowCodec
+
+use itertools::Itertools;
+
+use crate::{
+    draw::{csv, tsv},
+    viewer::{DecodeErrorBehavior, RowCodec},
+    DataTable, RowViewer,
+};
+
+/* --------------------------------------------- Options -------------------------------------------- */
+
+/// Text format used for bulk import / export.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TextFormat {
+    /// Tab separated values, where special characters are escaped with backslash. This is the
+    /// default format of clipboard.
+    #[default]
+    Tsv,
+
+    /// Tab separated values, where fields containing special characters are wrapped with
+    /// double quotes, in RFC 4180 manner. This is what most spreadsheet applications use for
+    /// clipboard.
+    QuotedTsv,
+
+    /// RFC 4180 comma separated values.
+    Csv,
+}
+
+impl TextFormat {
+    /// Field delimiter of quoted formats.
+    pub(crate) fn quoted_delimiter(&self) -> Option<char> {
+        match self {
+            TextFormat::Tsv => None,
+            TextFormat::QuotedTsv => Some('\t'),
+            TextFormat::Csv => Some(','),
+        }
+    }
+}
+
+/// Which part of the table is exported.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Every row in storage order, with every column in index order.
+    #[default]
+    All,
+
+    /// Rows as currently displayed; filtered and sorted, with visible columns only, in display
+    /// order. If the table was never rendered, this only applies [`RowViewer::filter_row`].
+    Visible,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExportOptions {
+    pub format: TextFormat,
+
+    /// Write column names from [`RowViewer::column_name`] as the first line.
+    pub header: bool,
+
+    pub scope: ExportScope,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: TextFormat::default(),
+            header: true,
+            scope: ExportScope::default(),
+        }
+    }
+}
+
+/// How imported rows are merged into the table.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Replace the whole table. As every row is replaced, undo history and selections are
+    /// cleared.
+    #[default]
+    Replace,
+
+    /// Append imported rows after the existing ones.
+    Append,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+    pub format: TextFormat,
+
+    /// Treat the first line as column names, which are matched against
+    /// [`RowViewer::column_name`]. Otherwise, fields are mapped to columns by index.
+    pub header: bool,
+
+    pub mode: ImportMode,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            format: TextFormat::default(),
+            header: true,
+            mode: ImportMode::default(),
+        }
+    }
+}
+
+/* --------------------------------------------- Results -------------------------------------------- */
+
+/// Summary of a successful import. Row indices count data records only; i.e. the header line
+/// is excluded.
+#[derive(Debug, Default, Clone)]
+pub struct ImportReport {
+    /// Number of rows actually added to the table.
+    pub num_imported_rows: usize,
+
+    /// Records skipped by [`DecodeErrorBehavior::SkipRow`].
+    pub skipped_rows: Vec<usize>,
+
+    /// Cells skipped by [`DecodeErrorBehavior::SkipCell`], as (record, column) pairs.
+    pub skipped_cells: Vec<(usize, usize)>,
+
+    /// Header names which didn't match any column. Their fields are ignored.
+    pub unknown_columns: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("the viewer does not provide a decoding codec")]
+    NoCodec,
+
+    #[error("malformed input text")]
+    Malformed,
+
+    #[error("decoding aborted at record {row}, column {column}")]
+    Aborted { row: usize, column: usize },
+}
+
+/* ------------------------------------------ Implementation ---------------------------------------- */
+
+impl<R> DataTable<R> {
+    /// Encode the table into text, using the viewer's encoding codec. Returns `None` if the
+    /// viewer does not provide one.
+    pub fn export_text<V: RowViewer<R>>(
+        &mut self,
+        viewer: &mut V,
+        options: &ExportOptions,
+    ) -> Option<String> {
+        let (rows, columns) = match options.scope {
+            ExportScope::All => (
+                (0..self.rows.len()).collect_vec(),
+                (0..viewer.num_columns()).collect_vec(),
+            ),
+            ExportScope::Visible => self.visible_view(viewer),
+        };
+
+        let mut buf_out = String::new();
+
+        if options.header {
+            for (index, column) in columns.iter().enumerate() {
+                write_field(&mut buf_out, options.format, index, &viewer.column_name(*column));
+            }
+
+            write_newline(&mut buf_out, options.format);
+        }
+
+        let mut codec = viewer.try_create_codec(true)?;
+        let mut buf_tmp = String::new();
+
+        for row in rows {
+            for (index, column) in columns.iter().enumerate() {
+                codec.encode_column(&self.rows[row], *column, &mut buf_tmp);
+                write_field(&mut buf_out, options.format, index, &buf_tmp);
+                buf_tmp.clear();
+            }
+
+            write_newline(&mut buf_out, options.format);
+        }
+
+        Some(buf_out)
+    }
+
+    /// Decode rows from text using the viewer's decoding codec, then put them into the table.
+    /// Decoding errors are handled as [`RowCodec::decode_column`] requests; skipped cells and
+    /// rows are listed in the returned report.
+    pub fn import_text<V: RowViewer<R>>(
+        &mut self,
+        viewer: &mut V,
+        text: &str,
+        options: &ImportOptions,
+    ) -> Result<ImportReport, ImportError> {
+        let parsed_tsv;
+        let parsed_csv;
+
+        let records: Vec<Vec<&str>> = match options.format.quoted_delimiter() {
+            None => {
+                parsed_tsv = tsv::ParsedTsv::parse(text);
+                parsed_tsv
+                    .iter_rows()
+                    .map(|(_, cells)| cells.map(|(_, data)| data).collect())
+                    .collect()
+            }
+            Some(delimiter) => {
+                parsed_csv =
+                    csv::ParsedCsv::parse(text, delimiter).ok_or(ImportError::Malformed)?;
+                parsed_csv
+                    .iter_rows()
+                    .map(|(_, cells)| cells.map(|(_, data, _)| data).collect())
+                    .collect()
+            }
+        };
+
+        let mut report = ImportReport::default();
+        let mut records = records.into_iter();
+
+        let column_map: Vec<Option<usize>> = if options.header {
+            let names = (0..viewer.num_columns())
+                .map(|column| viewer.column_name(column))
+                .collect_vec();
+
+            records
+                .next()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|name| {
+                    let name = name.trim();
+                    let column = names.iter().position(|x| x == name);
+
+                    if column.is_none() {
+                        report.unknown_columns.push(name.to_owned());
+                    }
+
+                    column
+                })
+                .collect()
+        } else {
+            (0..viewer.num_columns()).map(Some).collect()
+        };
+
+        let mut codec = viewer.try_create_codec(false).ok_or(ImportError::NoCodec)?;
+        let mut rows = Vec::new();
+
+        'records: for (row, fields) in records.enumerate() {
+            if fields.is_empty() {
+                // Blank line.
+                continue;
+            }
+
+            let mut dst_row = codec.create_empty_decoded_row();
+
+            for (data, column) in fields.into_iter().zip(&column_map) {
+                let Some(column) = *column else {
+                    continue;
+                };
+
+                match codec.decode_column(data, column, &mut dst_row) {
+                    Ok(()) => {}
+                    Err(DecodeErrorBehavior::SkipCell) => {
+                        report.skipped_cells.push((row, column));
+                    }
+                    Err(DecodeErrorBehavior::SkipRow) => {
+                        report.skipped_rows.push(row);
+                        continue 'records;
+                    }
+                    Err(DecodeErrorBehavior::Abort) => {
+                        return Err(ImportError::Aborted { row, column });
+                    }
+                }
+            }
+
+            rows.push(dst_row);
+        }
+
+        report.num_imported_rows = rows.len();
+
+        match options.mode {
+            ImportMode::Replace => {
+                self.reset_rows(rows);
+            }
+            ImportMode::Append => {
+                self.append(rows);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns (row ids, column indices) of the currently displayed table.
+    fn visible_view<V: RowViewer<R>>(&mut self, viewer: &mut V) -> (Vec<usize>, Vec<usize>) {
+        match self.ui.as_mut() {
+            Some(ui) => {
+                ui.validate_identity(viewer);
+                ui.validate_cc(&mut self.rows, viewer);
+
+                (ui.vis_row_ids().collect(), ui.vis_column_ids().collect())
+            }
+            None => (
+                (0..self.rows.len())
+                    .filter(|row| viewer.filter_row(&self.rows[*row]))
+                    .collect(),
+                (0..viewer.num_columns()).collect(),
+            ),
+        }
+    }
+}
+
+fn write_field(buf: &mut String, format: TextFormat, index: usize, data: &str) {
+    match format.quoted_delimiter() {
+        None => {
+            if index > 0 {
+                tsv::write_tab(buf);
+            }
+
+            tsv::write_content(buf, data);
+        }
+        Some(delimiter) => {
+            if index > 0 {
+                csv::write_delimiter(buf, delimiter);
+            }
+
+            csv::write_content(buf, data, delimiter);
+        }
+    }
+}
+
+fn write_newline(buf: &mut String, format: TextFormat) {
+    match format.quoted_delimiter() {
+        None => tsv::write_newline(buf),
+        Some(_) => csv::write_newline(buf),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::default;
+
+    /// Rows of (name, count), where counts fail to decode by `on_error`.
+    struct Viewer {
+        on_error: DecodeErrorBehavior,
+    }
+
+    struct Codec(DecodeErrorBehavior);
+
+    impl RowCodec<(String, u32)> for Codec {
+        type DeserializeError = ();
+
+        fn create_empty_decoded_row(&mut self) -> (String, u32) {
+            default()
+        }
+
+        fn encode_column(&mut self, src_row: &(String, u32), column: usize, dst: &mut String) {
+            match column {
+                0 => dst.push_str(&src_row.0),
+                _ => dst.push_str(&src_row.1.to_string()),
+            }
+        }
+
+        fn decode_column(
+            &mut self,
+            src_data: &str,
+            column: usize,
+            dst_row: &mut (String, u32),
+        ) -> Result<(), DecodeErrorBehavior> {
+            match column {
+                0 => dst_row.0 = src_data.to_owned(),
+                _ => dst_row.1 = src_data.parse().map_err(|_| self.0)?,
+            }
+
+            Ok(())
+        }
+    }
+
+    impl RowViewer<(String, u32)> for Viewer {
+        fn num_columns(&mut self) -> usize {
+            2
+        }
+
+        fn column_name(&mut self, column: usize) -> Cow<'static, str> {
+            ["name", "count"][column].into()
+        }
+
+        fn try_create_codec(&mut self, _: bool) -> Option<impl RowCodec<(String, u32)>> {
+            Some(Codec(self.on_error))
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &(String, u32), _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut (String, u32),
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &(String, u32), dst: &mut (String, u32), column: usize) {
+            match column {
+                0 => dst.0 = src.0.clone(),
+                _ => dst.1 = src.1,
+            }
+        }
+
+        fn new_empty_row(&mut self) -> (String, u32) {
+            default()
+        }
+    }
+
+    fn viewer() -> Viewer {
+        Viewer {
+            on_error: DecodeErrorBehavior::SkipCell,
+        }
+    }
+
+    fn table(rows: &[(&str, u32)]) -> DataTable<(String, u32)> {
+        rows.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+    }
+
+    fn import(
+        table: &mut DataTable<(String, u32)>,
+        vwr: &mut Viewer,
+        text: &str,
+        mode: ImportMode,
+    ) -> Result<ImportReport, ImportError> {
+        let options = ImportOptions {
+            mode,
+            ..default()
+        };
+
+        table.import_text(vwr, text, &options)
+    }
+
+    #[test]
+    fn export_with_header() {
+        let mut table = table(&[("a b", 1), ("c,d", 2)]);
+
+        let tsv = table.export_text(&mut viewer(), &default()).unwrap();
+        assert_eq!(tsv, "name\tcount\na b\t1\nc,d\t2\n");
+
+        let options = ExportOptions {
+            format: TextFormat::Csv,
+            header: false,
+            ..default()
+        };
+        let csv = table.export_text(&mut viewer(), &options).unwrap();
+        assert_eq!(csv, "a b,1\n\"c,d\",2\n");
+    }
+
+    #[test]
+    fn import_maps_header_to_columns() {
+        let mut table = table(&[]);
+        let report = import(
+            &mut table,
+            &mut viewer(),
+            "count\textra\tname\n3\tx\tc\n4\ty\td\n",
+            ImportMode::Replace,
+        )
+        .unwrap();
+
+        assert_eq!(report.num_imported_rows, 2);
+        assert_eq!(report.unknown_columns, ["extra"]);
+        assert_eq!(*table, [("c".into(), 3), ("d".into(), 4)]);
+    }
+
+    #[test]
+    fn import_replaces_or_appends() {
+        let mut table = table(&[("a", 1)]);
+
+        import(&mut table, &mut viewer(), "name\tcount\nb\t2\n", ImportMode::Append).unwrap();
+        assert_eq!(*table, [("a".into(), 1), ("b".into(), 2)]);
+
+        import(&mut table, &mut viewer(), "name\tcount\nc\t3\n", ImportMode::Replace).unwrap();
+        assert_eq!(*table, [("c".into(), 3)]);
+    }
+
+    #[test]
+    fn import_reports_decode_errors() {
+        const TEXT: &str = "name\tcount\na\t1\nb\tx\n\nc\t3\n";
+
+        let mut table = table(&[]);
+        let report = import(&mut table, &mut viewer(), TEXT, ImportMode::Replace).unwrap();
+        assert_eq!(report.num_imported_rows, 3);
+        assert_eq!(report.skipped_cells, [(1, 1)]);
+        assert!(report.skipped_rows.is_empty());
+        assert_eq!(*table, [("a".into(), 1), ("b".into(), 0), ("c".into(), 3)]);
+
+        let mut vwr = Viewer {
+            on_error: DecodeErrorBehavior::SkipRow,
+        };
+        let report = import(&mut table, &mut vwr, TEXT, ImportMode::Replace).unwrap();
+        assert_eq!(report.num_imported_rows, 2);
+        assert_eq!(report.skipped_rows, [1]);
+        assert_eq!(*table, [("a".into(), 1), ("c".into(), 3)]);
+
+        let mut vwr = Viewer {
+            on_error: DecodeErrorBehavior::Abort,
+        };
+        let result = import(&mut table, &mut vwr, TEXT, ImportMode::Append);
+        assert!(matches!(result, Err(ImportError::Aborted { row: 1, column: 1 })));
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut src = table(&[("quoted \"name\"", 1), ("multi\nline, text", 2)]);
+        let options = ExportOptions {
+            format: TextFormat::Csv,
+            ..default()
+        };
+        let text = src.export_text(&mut viewer(), &options).unwrap();
+
+        let mut dst = table(&[]);
+        let options = ImportOptions {
+            format: TextFormat::Csv,
+            ..default()
+        };
+        dst.import_text(&mut viewer(), &text, &options).unwrap();
+        assert_eq!(*dst, *src);
+    }
+}
@@ -1,10 +1,15 @@
 #![doc = include_str!("../README.md")]
 
 pub mod draw;
+pub mod history;
+pub mod io;
+pub mod source;
 pub mod viewer;
 
 pub use draw::{Renderer, Style};
-pub use viewer::{RowViewer, UiAction, CustomMenuItem, SelectionSnapshot, UserCommand};
+#[cfg(feature = "rayon")]
+pub use viewer::ParallelRowOps;
+pub use viewer::{BackgroundRowOps, CellStyle, CellValidation, FillSeries, RowGroup, RowViewer, UiAction, CustomMenuItem, CustomActionOrigin, SelectionSnapshot, UserCommand};
 
 /// You may want to sync egui version with this crate.
 pub extern crate egui;
@@ -76,6 +81,20 @@ impl<R> DataTable<R> {
         std::mem::replace(&mut self.rows, new)
     }
 
+    /// Appends rows at the end. Unlike modification through `DerefMut`, it keeps every UI state
+    /// as-is, and only the new rows are filtered and sorted into the view. Useful for streaming
+    /// data, e.g. log rows; see also [`Style::follow_tail`].
+    pub fn append(&mut self, rows: impl IntoIterator<Item = R>) {
+        let from = self.rows.len();
+        self.rows.extend(rows);
+
+        if let Some(ui) = self.ui.as_mut()
+            && self.rows.len() > from
+        {
+            ui.mark_rows_appended(from..self.rows.len());
+        }
+    }
+
     /// Insert a row at the specified index. This is thin wrapper of `Vec::retain` which provides
     /// additional dirty flag optimization.
     pub fn retain(&mut self, mut f: impl FnMut(&R) -> bool) {
@@ -91,6 +110,20 @@ impl<R> DataTable<R> {
         }
     }
 
+    /// Replaces every row. Keyed rows are matched with the replaced ones on next validation;
+    /// otherwise, every reference to rows is dropped.
+    pub(crate) fn reset_rows(&mut self, rows: Vec<R>) {
+        self.rows = rows;
+
+        if let Some(ui) = self.ui.as_mut() {
+            if ui.has_row_keys() {
+                ui.force_mark_dirty();
+            } else {
+                ui.invalidate_row_references();
+            }
+        }
+    }
+
     /// Check if the UI is obsolete and needs to be re-rendered due to data changes.
     pub fn is_dirty(&self) -> bool {
         self.ui.as_ref().is_some_and(|ui| ui.cc_is_dirty())
@@ -124,11 +157,9 @@ impl<R> DataTable<R> {
 }
 
 impl<R> Extend<R> for DataTable<R> {
-    /// Programmatic extend operation will invalidate the index table cache.
+    /// Same as [`DataTable::append`].
     fn extend<T: IntoIterator<Item = R>>(&mut self, iter: T) {
-        // Invalidate the cache
-        self.ui = None;
-        self.rows.extend(iter);
+        self.append(iter);
     }
 }
 
@@ -0,0 +1,445 @@
+//! Rows provided by an external data source, e.g. a database, rather than owned by
+//! [`DataTable`]. Rows are fetched lazily as they're rendered, and sort and filters are applied
+//! by the source.
+
+use std::ops::Range;
+
+use itertools::Itertools;
+
+use crate::{DataTable, RowViewer, UserCommand};
+
+/// Sort and filters configured by user, to be applied by [`RowSource`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowQuery {
+    /// Sort columns in order of priority, as `(column, is_ascending)`.
+    pub sort: Vec<(usize, bool)>,
+
+    /// Contents of the filter row, as `(column, filter)`. Filters of hidden columns are not
+    /// included. See [`crate::Style::show_filter_row`].
+    pub filters: Vec<(usize, String)>,
+}
+
+/// Source of rows rendered through [`SourceTable`]. Row indices refer to the result of the
+/// current query.
+///
+/// Rows are filtered by the source alone. [`crate::RowViewer::filter_row`] is not called for
+/// source-backed tables, and contents of the filter row are delivered in [`RowQuery::filters`].
+pub trait RowSource<R> {
+    /// Number of rows of the current query result.
+    fn num_rows(&mut self) -> usize;
+
+    /// Rows within `range` of the current query result, in order. The range never exceeds
+    /// [`RowSource::num_rows`].
+    fn fetch(&mut self, range: Range<usize>) -> Vec<R>;
+
+    /// Sorts and filters rows. Sorting is expected to be stable, as the table does for owned
+    /// rows.
+    fn set_query(&mut self, query: &RowQuery) {
+        let _ = query;
+    }
+
+    /// Applies a modification of rows made from UI, including undo and redo. Commands are
+    /// given in order, where each index reflects the preceding commands. Only
+    /// [`UserCommand::SetRowValue`], [`UserCommand::InsertRows`] and
+    /// [`UserCommand::RemoveRows`] are given.
+    fn apply(&mut self, command: UserCommand<R>);
+}
+
+/// Renders rows of [`RowSource`] through [`crate::Renderer::from_source`], fetching only the
+/// pages of rows being rendered.
+///
+/// The table holds a row for every row of the query result, where the ones not fetched yet are
+/// [`RowViewer::new_empty_row`]. Fetched rows are kept until the query changes. Rows modified
+/// from UI are fetched again with their page, as the source may normalize the values, while
+/// inserted or removed rows only shift the fetched ones.
+pub struct SourceTable<R, S> {
+    source: S,
+
+    /// Rows of the current query result.
+    table: DataTable<R>,
+
+    /// Whether each row of `table` is fetched from the source.
+    fetched: Vec<bool>,
+
+    page_size: usize,
+
+    /// Query given to the source last time.
+    query: RowQuery,
+
+    /// Whether `table` must be rebuilt from the source, e.g. as the query result is changed.
+    is_stale: bool,
+}
+
+impl<R, S: RowSource<R>> SourceTable<R, S> {
+    /// Rows are fetched `page_size` rows at a time.
+    pub fn new(source: S, page_size: usize) -> Self {
+        Self {
+            source,
+            table: DataTable::new(),
+            fetched: Vec::new(),
+            page_size: page_size.max(1),
+            query: Default::default(),
+            is_stale: true,
+        }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Call [`SourceTable::refresh`] after modifying rows of the source.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Rows of the current query result, including the ones not fetched yet.
+    pub fn table(&self) -> &DataTable<R> {
+        &self.table
+    }
+
+    /// Rows of the current query result. Modifications through [`DataTable::apply`] and
+    /// [`DataTable::edit`] are sent to the source as modifications from UI are; the ones
+    /// through `DerefMut` are not.
+    pub fn table_mut(&mut self) -> &mut DataTable<R> {
+        &mut self.table
+    }
+
+    /// Number of rows of the current query result, as of the last sync.
+    pub fn num_rows(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Whether the row has been fetched from the source, rather than being a placeholder.
+    pub fn is_fetched(&self, row: usize) -> bool {
+        self.fetched.get(row).copied().unwrap_or(false)
+    }
+
+    /// Discards every fetched row, to fetch them again on the next frame.
+    pub fn refresh(&mut self) {
+        self.is_stale = true;
+    }
+
+    /// Sends modifications from UI to the source, applies the query if it's changed by user,
+    /// then fetches pages of the rendered rows which are not fetched yet. Called by
+    /// [`crate::Renderer::from_source`] on every frame.
+    pub fn sync(&mut self, viewer: &mut impl RowViewer<R>) {
+        let ui = self.table.ui.get_or_insert_default();
+        ui.set_source_backed();
+
+        let edits = ui.take_source_edits();
+        let query = ui.source_query();
+        let rendered = ui.rendered_rows();
+
+        for edit in edits {
+            self.mark_edited(&edit);
+            self.source.apply(edit);
+        }
+
+        if query != self.query {
+            self.source.set_query(&query);
+            self.query = query;
+            self.is_stale = true;
+        }
+
+        if self.is_stale || self.source.num_rows() != self.table.len() {
+            let num_rows = self.source.num_rows();
+            let rows = (0..num_rows).map(|_| viewer.new_empty_row()).collect();
+
+            self.table.reset_rows(rows);
+            self.fetched = vec![false; num_rows];
+            self.is_stale = false;
+        }
+
+        // Nothing's rendered yet; the first page is likely to be.
+        let rendered = if rendered.is_empty() {
+            0..self.page_size
+        } else {
+            rendered
+        };
+
+        let first = rendered.start / self.page_size;
+        let last = rendered.end.div_ceil(self.page_size);
+
+        for page in first..last {
+            let start = page * self.page_size;
+            let end = (start + self.page_size).min(self.fetched.len());
+
+            if start >= end || self.fetched[start..end].iter().all(|x| *x) {
+                continue;
+            }
+
+            let rows = self.source.fetch(start..end);
+
+            for (index, row) in (start..end).zip(rows) {
+                // Keeps the rows edited since, which are fetched again once the edits are
+                // delivered.
+                if !self.fetched[index] {
+                    self.table[index] = row;
+                    self.fetched[index] = true;
+                }
+            }
+        }
+    }
+
+    /// Keeps `fetched` in line with the rows of the table, which are already modified by the
+    /// command. Modified rows are marked to be fetched again.
+    fn mark_edited(&mut self, command: &UserCommand<R>) {
+        match command {
+            UserCommand::SetRowValue(row, _) => self.fetched[*row] = false,
+            UserCommand::SetCells { values, .. } => {
+                for (row, ..) in values.iter() {
+                    self.fetched[*row] = false;
+                }
+            }
+            UserCommand::InsertRows(pos, rows) => {
+                self.fetched
+                    .splice(*pos..*pos, std::iter::repeat_n(false, rows.len()));
+            }
+            UserCommand::RemoveRows(rows) => {
+                let rows = rows.iter().copied().sorted_unstable().collect_vec();
+                let mut index = 0;
+
+                self.fetched.retain(|_| {
+                    index += 1;
+                    rows.binary_search(&(index - 1)).is_err()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tap::Tap;
+
+    use super::*;
+    use crate::draw::state::{Command, ColumnIdx, IsAscending, RowIdx};
+
+    /// Placeholder of rows not fetched yet.
+    const EMPTY: u32 = u32::MAX;
+
+    /// Rows in memory, which are sorted in place by the query.
+    #[derive(Default)]
+    struct Source {
+        rows: Vec<u32>,
+        fetched: Vec<Range<usize>>,
+        queries: Vec<RowQuery>,
+        applied: Vec<UserCommand<u32>>,
+    }
+
+    impl RowSource<u32> for Source {
+        fn num_rows(&mut self) -> usize {
+            self.rows.len()
+        }
+
+        fn fetch(&mut self, range: Range<usize>) -> Vec<u32> {
+            self.fetched.push(range.clone());
+            self.rows[range].to_vec()
+        }
+
+        fn set_query(&mut self, query: &RowQuery) {
+            if let Some((_, asc)) = query.sort.first() {
+                self.rows.sort_by(|a, b| if *asc { a.cmp(b) } else { b.cmp(a) });
+            }
+
+            self.queries.push(query.clone());
+        }
+
+        fn apply(&mut self, command: UserCommand<u32>) {
+            match &command {
+                UserCommand::SetRowValue(row, value) => self.rows[*row] = **value,
+                UserCommand::InsertRows(pos, rows) => {
+                    self.rows.splice(*pos..*pos, rows.iter().copied());
+                }
+                UserCommand::RemoveRows(rows) => {
+                    for row in rows.iter().rev() {
+                        self.rows.remove(*row);
+                    }
+                }
+                UserCommand::SetCells { .. } => unreachable!(),
+            }
+
+            self.applied.push(command);
+        }
+    }
+
+    struct Viewer;
+
+    impl RowViewer<u32> for Viewer {
+        fn num_columns(&mut self) -> usize {
+            1
+        }
+
+        fn is_sortable_column(&mut self, _: usize) -> bool {
+            true
+        }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &u32, _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut u32,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &u32, dst: &mut u32, _: usize) {
+            *dst = *src;
+        }
+
+        fn new_empty_row(&mut self) -> u32 {
+            EMPTY
+        }
+    }
+
+    /// 25 rows of 0..25, fetched in pages of 10 rows.
+    fn source_table() -> SourceTable<u32, Source> {
+        let source = Source {
+            rows: (0..25).collect(),
+            ..Default::default()
+        };
+
+        SourceTable::new(source, 10).tap_mut(|x| x.sync(&mut Viewer))
+    }
+
+    /// Syncs the table as the renderer does after rendering the rows.
+    fn render(table: &mut SourceTable<u32, Source>, rows: Range<usize>) {
+        let ui = table.table.ui.as_mut().unwrap();
+        ui.validate_identity(&mut Viewer);
+        ui.set_rendered_rows(rows);
+        table.sync(&mut Viewer);
+    }
+
+    fn push_command(table: &mut SourceTable<u32, Source>, command: Command<u32>) {
+        let mut ui = table.table.ui.take().unwrap();
+        ui.push_new_command(&mut table.table, &mut Viewer, command, 100);
+        table.table.ui = Some(ui);
+    }
+
+    /// Fetched rows are the ones of the source, at the same position.
+    fn assert_mirrored(table: &SourceTable<u32, Source>) {
+        assert_eq!(table.num_rows(), table.source().rows.len());
+
+        for (index, row) in table.table().iter().enumerate() {
+            if table.is_fetched(index) {
+                assert_eq!(*row, table.source().rows[index], "row {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn rendered_rows_are_fetched() {
+        let mut table = source_table();
+        assert_eq!(table.num_rows(), 25);
+        assert_eq!(table.source().fetched.len(), 1);
+        assert_eq!(table.source().fetched[0], 0..10);
+        assert_eq!(table.table()[..10], (0..10).collect::<Vec<_>>());
+        assert!(table.table()[10..].iter().all(|x| *x == EMPTY));
+
+        render(&mut table, 15..22);
+        assert_eq!(table.source().fetched, [0..10, 10..20, 20..25]);
+        assert_eq!(**table.table(), (0..25).collect::<Vec<_>>());
+
+        // Fetched rows are kept.
+        render(&mut table, 0..25);
+        assert_eq!(table.source().fetched.len(), 3);
+
+        table.refresh();
+        table.sync(&mut Viewer);
+        assert_eq!(table.source().fetched[3..], [0..10, 10..20, 20..25]);
+    }
+
+    #[test]
+    fn edited_rows_are_fetched_again() {
+        let mut table = source_table();
+        render(&mut table, 20..25);
+        assert_eq!(table.source().fetched, [0..10, 20..25]);
+
+        table.table_mut().apply(&mut Viewer, UserCommand::SetRowValue(22, 100.into()));
+        table.sync(&mut Viewer);
+        assert!(matches!(table.source().applied[..], [UserCommand::SetRowValue(22, _)]));
+        assert_eq!(table.source().fetched, [0..10, 20..25, 20..25]);
+        assert_mirrored(&table);
+
+        // Inserted or removed rows shift the fetched ones, which are not fetched again.
+        table.table_mut().apply(&mut Viewer, UserCommand::RemoveRows(vec![0, 1]));
+        table.table_mut().apply(&mut Viewer, UserCommand::InsertRows(0, [200].into()));
+        table.sync(&mut Viewer);
+        assert_eq!(table.source().fetched.len(), 3);
+        assert!(!table.is_fetched(0));
+        assert!((1..8).chain(20..24).all(|x| table.is_fetched(x)));
+        assert_mirrored(&table);
+
+        render(&mut table, 0..5);
+        assert_eq!(table.source().fetched.len(), 4);
+        assert_eq!(table.source().fetched[3], 0..10);
+        assert_eq!(table.table()[0], 200);
+
+        // Undo and redo are delivered as well.
+        let edited = table.source().rows.clone();
+        while table.table_mut().undo(&mut Viewer) {}
+        table.sync(&mut Viewer);
+        assert_eq!(table.source().rows, (0..25).collect::<Vec<_>>());
+        assert_mirrored(&table);
+
+        while table.table_mut().redo(&mut Viewer) {}
+        table.sync(&mut Viewer);
+        assert_eq!(table.source().rows, edited);
+        assert_mirrored(&table);
+    }
+
+    #[test]
+    fn moved_rows_are_removed_then_inserted() {
+        let mut table = source_table();
+        render(&mut table, 10..20);
+
+        let rows = |x: &[usize]| x.iter().copied().map(RowIdx::new).collect();
+        push_command(&mut table, Command::MoveRows {
+            from: rows(&[11, 12, 16]),
+            to: rows(&[15, 16, 17]),
+        });
+        assert_eq!(table.table()[10..20], [10, 13, 14, 15, 17, 11, 12, 16, 18, 19]);
+
+        table.sync(&mut Viewer);
+        assert!(matches!(table.source().applied[..], [
+            UserCommand::RemoveRows(ref rows),
+            UserCommand::InsertRows(15, _),
+            UserCommand::InsertRows(16, _),
+            UserCommand::InsertRows(17, _),
+        ] if *rows == [11, 12, 16]));
+        assert_mirrored(&table);
+
+        assert!(table.table_mut().undo(&mut Viewer));
+        table.sync(&mut Viewer);
+        assert_eq!(table.source().rows, (0..25).collect::<Vec<_>>());
+        assert_mirrored(&table);
+    }
+
+    #[test]
+    fn query_change_fetches_again() {
+        let mut table = source_table();
+        render(&mut table, 20..25);
+
+        push_command(
+            &mut table,
+            Command::SetColumnSort(vec![(ColumnIdx::new(0), IsAscending::new(false))]),
+        );
+        table.sync(&mut Viewer);
+
+        assert_eq!(table.source().queries, [RowQuery {
+            sort: vec![(0, false)],
+            filters: vec![],
+        }]);
+        assert_eq!(table.source().fetched, [0..10, 20..25, 20..25]);
+
+        // Rows of the previous query are discarded.
+        assert!(table.table()[..20].iter().all(|x| *x == EMPTY));
+        assert_eq!(table.table()[20..], [4, 3, 2, 1, 0]);
+    }
+}
@@ -18,7 +18,11 @@ pub struct SelectionSnapshot<'a, R> {
 }
 
 /// A menu item contributed by the RowViewer for the context menu.
+///
+/// Shortcut text of an item is taken from [`RowViewer::hotkeys`] bound to
+/// [`UiAction::Custom`] of the same id.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct CustomMenuItem {
     /// A stable identifier used to dispatch the action.
     pub id: &'static str,
@@ -28,14 +32,280 @@ pub struct CustomMenuItem {
     pub icon: Option<&'static str>,
     /// Whether the item is enabled in the current context.
     pub enabled: bool,
+    /// Check state of a toggle item; `None` for plain items. Invoking the item dispatches its
+    /// action as usual, where the viewer is expected to flip the state.
+    pub checked: Option<bool>,
+    /// Items of the submenu. An item with any children opens the submenu instead of
+    /// dispatching its own action.
+    pub children: Vec<CustomMenuItem>,
+    /// Whether the item is a separator line rather than an actual item.
+    pub is_separator: bool,
 }
 
 impl CustomMenuItem {
     pub fn new(id: &'static str, label: impl Into<String>) -> Self {
-        Self { id, label: label.into(), icon: None, enabled: true }
+        Self {
+            id,
+            label: label.into(),
+            icon: None,
+            enabled: true,
+            checked: None,
+            children: Vec::new(),
+            is_separator: false,
+        }
+    }
+
+    /// A submenu containing `children`.
+    pub fn submenu(label: impl Into<String>, children: Vec<CustomMenuItem>) -> Self {
+        Self { children, ..Self::new("", label) }
+    }
+
+    pub fn separator() -> Self {
+        Self { is_separator: true, ..Self::new("", "") }
     }
+
     pub fn icon(mut self, icon: &'static str) -> Self { self.icon = Some(icon); self }
     pub fn enabled(mut self, enabled: bool) -> Self { self.enabled = enabled; self }
+    pub fn checked(mut self, checked: bool) -> Self { self.checked = Some(checked); self }
+}
+
+/// Per-cell appearance returned by [`RowViewer::cell_style`]. Every field is optional; unset
+/// fields fall back to the table's default look.
+#[derive(Debug, Clone, Default)]
+pub struct CellStyle {
+    /// Background fill, painted under the selection highlight.
+    pub bg: Option<egui::Color32>,
+    /// Text color of non-interactive widgets, e.g. labels.
+    pub fg: Option<egui::Color32>,
+    /// Border stroke, painted over the selection highlight.
+    pub border: Option<egui::Stroke>,
+    /// Text shown when the cell is hovered.
+    pub tooltip: Option<String>,
+    /// Color of a small triangular marker at the top-right corner of the cell.
+    pub badge: Option<egui::Color32>,
+}
+
+impl CellStyle {
+    pub fn bg(mut self, color: egui::Color32) -> Self { self.bg = Some(color); self }
+    pub fn fg(mut self, color: egui::Color32) -> Self { self.fg = Some(color); self }
+    pub fn border(mut self, stroke: impl Into<egui::Stroke>) -> Self { self.border = Some(stroke.into()); self }
+    pub fn tooltip(mut self, text: impl Into<String>) -> Self { self.tooltip = Some(text.into()); self }
+    pub fn badge(mut self, color: egui::Color32) -> Self { self.badge = Some(color); self }
+}
+
+/// Rows sharing the same group key. See [`RowViewer::group_key`].
+#[derive(Debug)]
+pub struct RowGroup<'a, R> {
+    pub key: &'a str,
+    pub is_collapsed: bool,
+    pub(crate) table: &'a [R],
+    pub(crate) row_ids: &'a [crate::draw::state::RowIdx],
+}
+
+impl<'a, R> RowGroup<'a, R> {
+    /// Every row in the group in display order, including hidden ones when collapsed.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &'a R> + use<'a, R> {
+        let table = self.table;
+        self.row_ids.iter().map(move |x| &table[x.index()])
+    }
+
+    /// Number of rows in the group.
+    pub fn len(&self) -> usize {
+        self.row_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.row_ids.is_empty()
+    }
+}
+
+/// A cell being filled by dragging the fill handle. See [`RowViewer::fill_series`].
+#[derive(Debug)]
+pub struct FillSeries<'a, R> {
+    /// Source cells as `(row, column)`, ordered toward the fill direction. When filling up or
+    /// left, the cell adjacent to the filled area comes last.
+    pub sources: &'a [(&'a R, usize)],
+    /// Position of the target cell in the series; the first filled cell is at `sources.len()`.
+    pub index: usize,
+    /// Row of the target cell, before filling.
+    pub target: &'a R,
+    /// Column of the target cell.
+    pub column: usize,
+}
+
+/// Thread-safe counterparts of [`RowViewer::compare_cell`], [`RowViewer::filter_row`] and
+/// [`RowViewer::filter_cell`], which filter and sort rows of large tables in parallel. Each of
+/// them must behave the same as the viewer's. See [`RowViewer::parallel_row_ops`].
+#[cfg(feature = "rayon")]
+pub struct ParallelRowOps<'a, R> {
+    pub(crate) compare: CompareCellFn<'a, R>,
+    pub(crate) filter_row: Option<FilterRowFn<'a, R>>,
+    pub(crate) filter_cell: Option<FilterCellFn<'a, R>>,
+
+    // Instantiated where `R: Sync` is known, as the table itself doesn't require it.
+    pub(crate) filter_rows: ParFilterRowsFn<R>,
+    pub(crate) filter_cells: ParFilterCellsFn<R>,
+    pub(crate) sort_rows: ParSortRowsFn<R>,
+}
+
+#[cfg(feature = "rayon")]
+type CompareCellFn<'a, R> = Box<dyn Fn(&R, &R, usize) -> std::cmp::Ordering + Sync + 'a>;
+
+#[cfg(feature = "rayon")]
+type FilterRowFn<'a, R> = Box<dyn Fn(&R) -> bool + Sync + 'a>;
+
+#[cfg(feature = "rayon")]
+type FilterCellFn<'a, R> = Box<dyn Fn(&R, usize, &str, Option<&str>) -> bool + Sync + 'a>;
+
+#[cfg(feature = "rayon")]
+type ParFilterRowsFn<R> = for<'a> fn(
+    &ParallelRowOps<'a, R>,
+    &[R],
+    std::ops::Range<usize>,
+) -> Vec<crate::draw::state::RowIdx>;
+
+#[cfg(feature = "rayon")]
+type ParFilterCellsFn<R> = for<'a, 'b, 'c> fn(
+    &ParallelRowOps<'a, R>,
+    &[R],
+    Vec<crate::draw::state::RowIdx>,
+    &[&'b (crate::draw::state::ColumnIdx, String)],
+    Option<&'c [String]>,
+) -> Vec<crate::draw::state::RowIdx>;
+
+#[cfg(feature = "rayon")]
+type ParSortRowsFn<R> = for<'a> fn(
+    &ParallelRowOps<'a, R>,
+    &[(crate::draw::state::ColumnIdx, crate::draw::state::IsAscending)],
+    &[R],
+    &mut [crate::draw::state::RowIdx],
+);
+
+#[cfg(feature = "rayon")]
+impl<'a, R: Sync> ParallelRowOps<'a, R> {
+    /// Rows are sorted in parallel with `compare`, which is [`RowViewer::compare_cell`].
+    pub fn new(compare: impl Fn(&R, &R, usize) -> std::cmp::Ordering + Sync + 'a) -> Self {
+        Self {
+            compare: Box::new(compare),
+            filter_row: None,
+            filter_cell: None,
+            filter_rows: crate::draw::state::par_filter_rows::<R>,
+            filter_cells: crate::draw::state::par_filter_cells::<R>,
+            sort_rows: crate::draw::state::par_sort_rows::<R>,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, R> ParallelRowOps<'a, R> {
+    /// [`RowViewer::filter_row`]. Unless it's set, rows are filtered on the UI thread. Pass
+    /// `|_| true` if the viewer doesn't override it.
+    pub fn filter_row(mut self, filter: impl Fn(&R) -> bool + Sync + 'a) -> Self {
+        self.filter_row = Some(Box::new(filter));
+        self
+    }
+
+    /// [`RowViewer::filter_cell`], given the same `encoded` cell. Cells are encoded on the UI
+    /// thread, between filtering by `filter_row` and by this. Unless it's set, rows are filtered
+    /// on the UI thread while any column filter is in use.
+    pub fn filter_cell(
+        mut self,
+        filter: impl Fn(&R, usize, &str, Option<&str>) -> bool + Sync + 'a,
+    ) -> Self {
+        self.filter_cell = Some(Box::new(filter));
+        self
+    }
+}
+
+/// Owned, thread-safe counterparts of [`RowViewer::compare_cell`], [`RowViewer::filter_row`] and
+/// [`RowViewer::filter_cell`], which filter and sort rows of large tables on a background thread.
+/// They work on keys of rows, `K`, taken by the function given to [`BackgroundRowOps::new`]; each
+/// of them must behave on the keys the same as the viewer's on the rows. See
+/// [`RowViewer::background_row_ops`].
+pub struct BackgroundRowOps<R, K> {
+    pub(crate) key: RowKeyFn<R, K>,
+    pub(crate) compare: SharedCompareCellFn<K>,
+    pub(crate) filter_row: Option<SharedFilterRowFn<K>>,
+    pub(crate) filter_cell: Option<SharedFilterCellFn<K>>,
+    pub(crate) min_rows: usize,
+}
+
+type RowKeyFn<R, K> = Box<dyn Fn(&R) -> K>;
+
+type SharedCompareCellFn<K> = Box<dyn Fn(&K, &K, usize) -> std::cmp::Ordering + Send>;
+
+type SharedFilterRowFn<K> = Box<dyn Fn(&K) -> bool + Send>;
+
+type SharedFilterCellFn<K> = Box<dyn Fn(&K, usize, &str, Option<&str>) -> bool + Send>;
+
+impl<R, K: Send + 'static> BackgroundRowOps<R, K> {
+    /// Keys are taken from every row by `key` on the UI thread, then sorted with `compare`, which
+    /// is [`RowViewer::compare_cell`]. Keys should hold only what comparison and filters need,
+    /// e.g. values of sortable columns, as taking them is the part left on the UI thread.
+    pub fn new(
+        key: impl Fn(&R) -> K + 'static,
+        compare: impl Fn(&K, &K, usize) -> std::cmp::Ordering + Send + 'static,
+    ) -> Self {
+        Self {
+            key: Box::new(key),
+            compare: Box::new(compare),
+            filter_row: None,
+            filter_cell: None,
+            min_rows: 10_000,
+        }
+    }
+}
+
+impl<R, K> BackgroundRowOps<R, K> {
+    /// [`RowViewer::filter_row`]. Unless it's set, rows are rebuilt on the UI thread. Pass
+    /// `|_| true` if the viewer doesn't override it.
+    pub fn filter_row(mut self, filter: impl Fn(&K) -> bool + Send + 'static) -> Self {
+        self.filter_row = Some(Box::new(filter));
+        self
+    }
+
+    /// [`RowViewer::filter_cell`], given the same `encoded` cell. Cells of filtered columns are
+    /// encoded on the UI thread along with keys. Unless it's set, rows are rebuilt on the UI
+    /// thread while any column filter is in use.
+    pub fn filter_cell(
+        mut self,
+        filter: impl Fn(&K, usize, &str, Option<&str>) -> bool + Send + 'static,
+    ) -> Self {
+        self.filter_cell = Some(Box::new(filter));
+        self
+    }
+
+    /// Tables with fewer rows are rebuilt on the UI thread, as it's cheaper than taking keys of
+    /// rows for the background thread. Defaults to 10 000.
+    pub fn min_rows(mut self, min_rows: usize) -> Self {
+        self.min_rows = min_rows;
+        self
+    }
+}
+
+/// Result of [`RowViewer::validate_cell`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CellValidation {
+    #[default]
+    Ok,
+    /// The value is accepted, but marked with a warning badge.
+    Warning(String),
+    /// The value is invalid; marked with an error badge.
+    Error(String),
+}
+
+impl CellValidation {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error(_))
+    }
+
+    /// Message of the warning or error.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Ok => None,
+            Self::Warning(msg) | Self::Error(msg) => Some(msg),
+        }
+    }
 }
 
 /// A user-issued command returned by custom actions. This will be translated into
@@ -65,18 +335,45 @@ pub struct CustomActionContext<'a, R> {
     pub selection: SelectionSnapshot<'a, R>,
     /// The cell where the action originates (row_id, column), if applicable.
     pub origin_cell: Option<(usize, usize)>,
+    /// The menu the action was invoked from.
+    pub origin: CustomActionOrigin,
+}
+
+/// Where a custom action was invoked from. See [`CustomActionContext::origin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum CustomActionOrigin {
+    /// Cell context menu, or any other trigger such as hotkeys.
+    #[default]
+    Cell,
+    /// Column header context menu of the column.
+    ColumnHeader(usize),
+    /// Row header context menu of the row id.
+    RowHeader(usize),
 }
 
 /// A builder-style editor that lets custom actions queue undoable changes in an ergonomic way.
+///
+/// Every queued change is undone at once, as a single undo history entry. Use
+/// [`CustomActionEditor::new_undo_group`] to split them into multiple entries.
 #[derive(Debug)]
 pub struct CustomActionEditor<R> {
     cmds: Vec<UserCommand<R>>,
     slab: Vec<R>,
     values: Vec<(usize, usize, usize)>,
+
+    /// Start indices of undo groups in `cmds`, except the first one.
+    group_starts: Vec<usize>,
+}
+
+impl<R> Default for CustomActionEditor<R> {
+    fn default() -> Self { Self::new() }
 }
 
 impl<R> CustomActionEditor<R> {
-    pub fn new() -> Self { Self { cmds: Vec::new(), slab: Vec::new(), values: Vec::new() } }
+    pub fn new() -> Self {
+        Self { cmds: Vec::new(), slab: Vec::new(), values: Vec::new(), group_starts: Vec::new() }
+    }
 
     /// Queue a single cell write. Provide a row object whose relevant column is set.
     /// The write will be applied to only the given column via RowViewer::set_cell_value.
@@ -116,11 +413,36 @@ impl<R> CustomActionEditor<R> {
         self
     }
 
+    /// Commit pending cell writes, then start a new undo group. Changes queued after this call
+    /// are recorded as a separate undo history entry.
+    pub fn new_undo_group(&mut self) -> &mut Self {
+        self.commit_cells(None);
+        if self.group_starts.last().copied().unwrap_or(0) != self.cmds.len() {
+            self.group_starts.push(self.cmds.len());
+        }
+        self
+    }
+
     /// Consume the editor and return all queued commands (auto-commits pending cells without context).
     pub fn into_commands(mut self) -> Vec<UserCommand<R>> {
         self.commit_cells(None);
         self.cmds
     }
+
+    /// Same as [`Self::into_commands`], but split by undo groups.
+    pub(crate) fn into_command_groups(mut self) -> Vec<Vec<UserCommand<R>>> {
+        self.commit_cells(None);
+
+        let mut groups = Vec::with_capacity(self.group_starts.len() + 1);
+        for start in self.group_starts.into_iter().rev() {
+            groups.push(self.cmds.split_off(start));
+        }
+
+        groups.push(self.cmds);
+        groups.reverse();
+        groups.retain(|group| !group.is_empty());
+        groups
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
@@ -212,7 +534,8 @@ pub trait RowViewer<R>: 'static {
         None::<()>
     }
 
-    /// Returns the rendering configuration for the column.
+    /// Returns the rendering configuration for the column. Other columns than the last visible
+    /// one are rendered with this until resized or auto-fit by user; the last one always is.
     fn column_render_config(
         &mut self,
         column: usize,
@@ -254,6 +577,12 @@ pub trait RowViewer<R>: 'static {
         true
     }
 
+    /// Returns if selected rows can be moved by dragging their row headers. Rows are movable
+    /// only while the table is neither sorted nor grouped.
+    fn allow_row_reordering(&mut self) -> bool {
+        false
+    }
+
     /// Compare two column contents for sort.
     fn compare_cell(&self, row_a: &R, row_b: &R, column: usize) -> std::cmp::Ordering {
         let _ = (row_a, row_b, column);
@@ -271,6 +600,135 @@ pub trait RowViewer<R>: 'static {
         true
     }
 
+    /// Thread-safe versions of filtering and comparison, to filter and sort rows in parallel.
+    /// Worth it for large tables, as rows are filtered and sorted on the UI thread otherwise.
+    #[cfg(feature = "rayon")]
+    fn parallel_row_ops(&self) -> Option<ParallelRowOps<'_, R>> {
+        None
+    }
+
+    /// Owned, thread-safe versions of filtering and comparison, to filter and sort rows of large
+    /// tables on a background thread. While rows are rebuilt, the table keeps rendering the
+    /// previous ones with a progress indicator in the top-left header cell. The thread gets keys
+    /// of rows taken by [`BackgroundRowOps::new`], rather than the rows. Not used while rows are
+    /// grouped.
+    fn background_row_ops(&self) -> Option<BackgroundRowOps<R, impl Send + 'static>> {
+        None::<BackgroundRowOps<R, ()>>
+    }
+
+    /// Returns if given column shows a filter editor in the header filter row. See
+    /// [`crate::Style::show_filter_row`].
+    fn is_filterable_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        false
+    }
+
+    /// Test a cell against the text entered in the filter row of its column. This is only
+    /// called for visible, filterable columns with non-empty filter text; rows that fail any
+    /// of them will be hidden, in addition to [`RowViewer::filter_row`].
+    ///
+    /// `encoded` is the cell encoded by the encoding codec, which is created once for every
+    /// rebuild of the view. It's `None` without a codec.
+    ///
+    /// Default implementation performs case-insensitive substring match on `encoded`. Without a
+    /// codec, every cell passes.
+    fn filter_cell(&mut self, row: &R, column: usize, filter: &str, encoded: Option<&str>) -> bool {
+        let _ = (row, column);
+        encoded.is_none_or(|text| text.to_lowercase().contains(&filter.to_lowercase()))
+    }
+
+    /// Text of the cell to be searched by the find & replace overlay (Ctrl+F). Return `None` to
+    /// exclude the cell from search.
+    ///
+    /// `encoded` is the cell encoded by the encoding codec, which is created once for a batch of
+    /// searched cells. It's `None` without a codec.
+    ///
+    /// Default implementation returns `encoded`. Replacing relies on the decoding codec, thus
+    /// cells are searchable but not replaceable without it.
+    fn cell_search_text(&mut self, row: &R, column: usize, encoded: Option<&str>) -> Option<String> {
+        let _ = (row, column);
+        encoded.map(str::to_owned)
+    }
+
+    /// Computes the value of a cell filled by dragging the fill handle at the bottom-right corner
+    /// of the selection. Returns a row whose `fill.column` holds the new value, which is then
+    /// written to the target through [`RowViewer::set_cell_value`]. Return `None` to leave the
+    /// cell as is.
+    ///
+    /// Default implementation repeats the source cells. Values are moved across columns through
+    /// the codec, thus horizontal fills are no-op without it. Override this to fill series such
+    /// as incrementing numbers or dates.
+    fn fill_series(&mut self, fill: &FillSeries<'_, R>) -> Option<R> {
+        let (src, src_column) = fill.sources[fill.index % fill.sources.len()];
+
+        if src_column == fill.column {
+            return Some(self.clone_row_as_copied_base(src));
+        }
+
+        let mut buf = String::new();
+        self.try_create_codec(true)?
+            .encode_column(src, src_column, &mut buf);
+
+        let mut dst = self.clone_row(fill.target);
+        self.try_create_codec(false)?
+            .decode_column(&buf, fill.column, &mut dst)
+            .ok()?;
+
+        Some(dst)
+    }
+
+    /// Number of leading visible columns which are frozen by default; frozen columns stay in
+    /// place while the table scrolls horizontally. Users can change this from the column header
+    /// context menu, and the change is persisted along with other UI states.
+    fn frozen_columns(&mut self) -> usize {
+        0
+    }
+
+    /// Number of leading rows, in display order, which are frozen by default; frozen rows stay
+    /// in place while the table scrolls vertically. See [`RowViewer::frozen_columns`].
+    fn frozen_rows(&mut self) -> usize {
+        0
+    }
+
+    /// Returns if rows can be grouped by the column, from the column header context menu.
+    fn is_groupable_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        false
+    }
+
+    /// Column to group rows by default. Users can change this from the column header context
+    /// menu, and the change is persisted along with other UI states.
+    fn group_by_column(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// Key of the group which the row belongs to, when grouped by the column. Groups are ordered
+    /// by their first row in the sorted order.
+    ///
+    /// `encoded` is the cell encoded by the encoding codec, which is created once for every
+    /// regrouping. It's `None` without a codec.
+    ///
+    /// Default implementation uses [`RowViewer::cell_search_text`].
+    fn group_key(&mut self, row: &R, column: usize, encoded: Option<&str>) -> String {
+        self.cell_search_text(row, column, encoded).unwrap_or_default()
+    }
+
+    /// Renders the label of a group header, next to the collapse button. Cells of the group
+    /// header under each column show [`RowViewer::column_aggregate`] of the group rows.
+    fn show_group_header(&mut self, ui: &mut egui::Ui, group: &RowGroup<'_, R>) {
+        ui.horizontal(|ui| {
+            ui.strong(group.key);
+            ui.weak(format!("({})", group.len()));
+        });
+    }
+
+    /// Aggregated value of the column over given rows, e.g. sum or average. Return `None` to
+    /// leave it empty.
+    fn column_aggregate(&mut self, rows: &[&R], column: usize) -> Option<String> {
+        let _ = (rows, column);
+        None
+    }
+
     /// Display values of the cell. Any input will be consumed before table renderer;
     /// therefore any widget rendered inside here is read-only.
     ///
@@ -279,6 +737,16 @@ pub trait RowViewer<R>: 'static {
     /// containing cell.
     fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &R, column: usize);
 
+    /// Conditional formatting of the cell. Prefer this over painting inside
+    /// [`RowViewer::show_cell_view`], as the renderer layers it with selection highlight: the
+    /// background goes under the highlight, while the border and badge go over it. The text
+    /// color of the interactive cell is overridden by [`crate::Style::fg_selected_highlight_cell`],
+    /// if set.
+    fn cell_style(&mut self, row: &R, column: usize) -> CellStyle {
+        let _ = (row, column);
+        CellStyle::default()
+    }
+
     /// Use this to check if given cell is going to take any dropped payload / use as drag
     /// source.
     fn on_cell_view_response(
@@ -315,6 +783,25 @@ pub trait RowViewer<R>: 'static {
         true
     }
 
+    /// Validates the value of the cell. Cells which are not [`CellValidation::Ok`] are marked
+    /// with a badge, and the message is shown as a tooltip. This is called for every visible
+    /// cell on each frame, and for the cell being edited.
+    fn validate_cell(&mut self, row: &R, column: usize) -> CellValidation {
+        let _ = (row, column);
+        CellValidation::Ok
+    }
+
+    /// If true, committing a cell edition is refused while [`RowViewer::validate_cell`] returns
+    /// [`CellValidation::Error`] for the edited cell, or for any other cell of the row changed by
+    /// the edition; the editor is kept open on the first invalid cell until the value gets valid
+    /// or the edition is canceled.
+    ///
+    /// While the editor is kept open, UI actions which would change rows or the selection are
+    /// ignored. Changes of the view, i.e. sort and column visibility or order, are still applied.
+    fn reject_invalid_edits(&mut self) -> bool {
+        false
+    }
+
     /// Before removing each row, this method is called to confirm the deletion from the
     /// viewer. This won't be called during the undo/redo operation!
     fn confirm_row_deletion_by_ui(&mut self, row: &R) -> bool {
@@ -353,6 +840,17 @@ pub trait RowViewer<R>: 'static {
         self.clone_row(row)
     }
 
+    /// Stable identity of the row, which must be unique within the table. Once provided,
+    /// selection, edition and undo history follow rows across modification of the table from
+    /// outside of UI, e.g. through `DerefMut` or `Extend`, and across re-sorts. Otherwise, rows
+    /// are tracked by their index, which such modification invalidates.
+    ///
+    /// Return `None` for every row to disable the tracking.
+    fn row_key(&mut self, row: &R) -> Option<u64> {
+        let _ = row;
+        None
+    }
+
     /// Called when a cell is selected/highlighted.
     fn on_highlight_cell(&mut self, row: &R, column: usize) {
         let _ = (row, column);
@@ -378,6 +876,12 @@ pub trait RowViewer<R>: 'static {
         let (_, _) = (row_index, row);
     }
 
+    /// Called for each row moved by dragging, including when undoing/redoing. `from` and `to`
+    /// are the row indices before and after the move.
+    fn on_row_moved(&mut self, from: usize, to: usize, row: &R) {
+        let (_, _, _) = (from, to, row);
+    }
+
     /// Return hotkeys for the current context.
     fn hotkeys(&mut self, context: &UiActionContext) -> Vec<(egui::KeyboardShortcut, UiAction)> {
         self::default_hotkeys(context)
@@ -400,6 +904,22 @@ pub trait RowViewer<R>: 'static {
         Vec::new()
     }
 
+    /// Provide custom items for the context menu of the column header. Invoked items are
+    /// dispatched through [`RowViewer::on_custom_action_ex`], with
+    /// [`CustomActionOrigin::ColumnHeader`].
+    fn column_header_context_menu_items(&mut self, column: usize) -> Vec<CustomMenuItem> {
+        let _ = column;
+        Vec::new()
+    }
+
+    /// Provide custom items for the context menu opened from the header of the row. Invoked
+    /// items are dispatched through [`RowViewer::on_custom_action_ex`], with
+    /// [`CustomActionOrigin::RowHeader`].
+    fn row_header_context_menu_items(&mut self, row_id: usize, row: &R) -> Vec<CustomMenuItem> {
+        let _ = (row_id, row);
+        Vec::new()
+    }
+
     /// Handle a custom action invoked from the context menu or other triggers.
     /// Return high-level user commands which will be translated into internal commands
     /// and integrated with undo/redo.
@@ -434,6 +954,12 @@ pub enum CellWriteContext {
 
     /// Value is being cleared by cut/delete operation.
     Clear,
+
+    /// Value is being replaced from the find & replace overlay.
+    Replace,
+
+    /// Value is being filled by dragging the fill handle.
+    Fill,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -515,6 +1041,9 @@ pub enum UiAction {
     SelectionDuplicateValues,
     SelectAll,
 
+    /// Opens the find & replace overlay.
+    Find,
+
     /// Custom action contributed by the RowViewer. Carries a stable action id.
     Custom(&'static str),
 }
@@ -572,6 +1101,7 @@ pub fn default_hotkeys(context: &UiActionContext) -> Vec<(KeyboardShortcut, UiAc
             (ctrl | shift, Key::D, UiAction::DuplicateRow),
             (ctrl, Key::D, UiAction::SelectionDuplicateValues),
             (ctrl, Key::A, UiAction::SelectAll),
+            (ctrl, Key::F, UiAction::Find),
             (ctrl, Key::Delete, UiAction::DeleteRow),
             (none, Key::Delete, UiAction::DeleteSelection),
             (none, Key::Backspace, UiAction::DeleteSelection),